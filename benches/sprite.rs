@@ -1,32 +1,106 @@
+use bytemuck::Zeroable;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use vulpo::backend::sprite::Sprites;
+use rayon::iter::ParallelIterator;
+use vulpo::backend::sprite::{SpriteHandle, Sprites};
+use vulpo::backend::vertex::Vertex;
 
-fn generate_sprites(n: usize) -> Sprites {
+fn add_sprite(sprites: &mut Sprites, i: usize) -> SpriteHandle {
+    sprites.add(
+        0,
+        ultraviolet::Vec2::new(0.0, 0.0),
+        ultraviolet::Vec2::new(128.0, 128.0),
+        ultraviolet::Vec2::new(128.0 * i as f32, 128.0 * i as f32),
+        i as f32,
+        ultraviolet::Vec2::new(i as f32, i as f32),
+        1.0,
+        [1.0, 1.0, 1.0, 1.0],
+        ultraviolet::Vec2::new(0.0, 0.0),
+    )
+}
+
+fn generate_sprites(n: usize) -> (Sprites, Vec<SpriteHandle>) {
     let mut sprites = Sprites::new();
-    for i in 0..n {
-        sprites.add(
-            0,
-            ultraviolet::Vec2::new(0.0, 0.0),
-            ultraviolet::Vec2::new(128.0, 128.0),
-            ultraviolet::Vec2::new(128.0 * i as f32, 128.0 * i as f32),
-            i as f32,
-            ultraviolet::Vec2::new(i as f32, i as f32),
-            1.0,
-            [1.0, 1.0, 1.0, 1.0],
-            ultraviolet::Vec2::new(0.0, 0.0),
-        );
-    }
-
-    sprites
+    let handles = (0..n).map(|i| add_sprite(&mut sprites, i)).collect();
+
+    (sprites, handles)
 }
 
 fn vertex_generation_benches(c: &mut Criterion) {
-    let sprites = generate_sprites(200_000);
+    let (sprites, _) = generate_sprites(200_000);
+    let index_format = sprites.index_format();
 
     c.bench_function("two_hundred_thousand_sprites", |b| {
-        b.iter(|| black_box(sprites.vertices_indices(128, 128)))
+        b.iter(|| black_box(sprites.vertices_indices(&[(128, 128)], index_format)))
+    });
+
+    c.bench_function("two_hundred_thousand_sprites_into_a_slice", |b| {
+        let mut vertices = vec![Vertex::zeroed(); sprites.len() * 4];
+        b.iter(|| sprites.write_vertices(0..sprites.len(), &[(128, 128)], &mut vertices))
+    });
+
+    c.bench_function("two_hundred_thousand_sprites_into_a_slice_wide", |b| {
+        let mut vertices = vec![Vertex::zeroed(); sprites.len() * 4];
+        b.iter(|| sprites.write_vertices_wide(0..sprites.len(), &[(128, 128)], &mut vertices))
+    });
+
+    c.bench_function("two_hundred_thousand_sprite_instances", |b| {
+        b.iter(|| black_box(sprites.instances(0..sprites.len(), &[(128, 128)])))
+    });
+}
+
+fn churn_benches(c: &mut Criterion) {
+    c.bench_function("despawn_respawn_ten_thousand_of_two_hundred_thousand", |b| {
+        let (mut sprites, mut handles) = generate_sprites(200_000);
+
+        b.iter(|| {
+            // Every 20th sprite dies and a new one takes its freed slot
+            for (i, handle) in handles.iter_mut().enumerate().step_by(20) {
+                sprites.remove(*handle);
+                *handle = add_sprite(&mut sprites, i);
+            }
+        })
+    });
+
+    c.bench_function("despawn_all_then_refill_fifty_thousand", |b| {
+        let (mut sprites, mut handles) = generate_sprites(50_000);
+
+        b.iter(|| {
+            for handle in handles.drain(..) {
+                sprites.remove(handle);
+            }
+            handles.extend((0..50_000).map(|i| add_sprite(&mut sprites, i)));
+            black_box(sprites.len())
+        })
+    });
+}
+
+fn bulk_update_benches(c: &mut Criterion) {
+    c.bench_function("move_one_hundred_thousand_one_by_one", |b| {
+        let (mut sprites, handles) = generate_sprites(100_000);
+
+        b.iter(|| {
+            for handle in &handles {
+                let position = sprites.position(*handle).unwrap();
+                sprites.set_position(*handle, position + ultraviolet::Vec2::new(1.0, 0.0));
+            }
+            sprites.update_transforms();
+        })
+    });
+
+    c.bench_function("move_one_hundred_thousand_through_columns", |b| {
+        let (mut sprites, _) = generate_sprites(100_000);
+
+        b.iter(|| {
+            let length = sprites.len();
+            sprites
+                .columns_mut(0..length)
+                .par_iter_mut()
+                .for_each(|(position, _, _, _)| position.x += 1.0);
+        })
     });
 }
 
 criterion_group!(sprite_vertex_generation, vertex_generation_benches);
-criterion_main!(sprite_vertex_generation);
+criterion_group!(sprite_churn, churn_benches);
+criterion_group!(sprite_bulk_updates, bulk_update_benches);
+criterion_main!(sprite_vertex_generation, sprite_churn, sprite_bulk_updates);
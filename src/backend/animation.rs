@@ -0,0 +1,453 @@
+use crate::backend::sprite::{SpriteHandle, Sprites};
+use std::collections::HashMap;
+
+/// One frame of a [`Clip`]: a source rectangle and how long it's shown.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// Top left corner of the source rectangle in texels.
+    pub source_position: ultraviolet::Vec2,
+    /// Size of the source rectangle in texels.
+    pub source_size: ultraviolet::Vec2,
+    /// In seconds.
+    pub duration: f32,
+    /// Origin the sprite switches to along with the frame, for frames trimmed by a sheet
+    /// exporter that would jump around otherwise. `None` leaves the origin alone.
+    pub origin: Option<ultraviolet::Vec2>,
+    /// Angle in degrees the sprite switches to along with the frame, for frames a sheet
+    /// packer stored rotated. `None` leaves the angle alone.
+    pub angle: Option<f32>,
+}
+
+impl Frame {
+    pub fn new(
+        source_position: ultraviolet::Vec2,
+        source_size: ultraviolet::Vec2,
+        duration: f32,
+    ) -> Self {
+        Self {
+            source_position,
+            source_size,
+            duration,
+            origin: None,
+            angle: None,
+        }
+    }
+
+    pub fn with_origin(mut self, origin: ultraviolet::Vec2) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = Some(angle);
+        self
+    }
+
+    /// Switches `sprite` to the frame, `None` if it was removed.
+    fn apply(&self, sprites: &mut Sprites, sprite: SpriteHandle) -> Option<()> {
+        sprites.set_source_position(sprite, self.source_position)?;
+        sprites.set_source_size(sprite, self.source_size)?;
+        if let Some(origin) = self.origin {
+            sprites.set_origin(sprite, origin)?;
+        }
+        if let Some(angle) = self.angle {
+            sprites.set_angle(sprite, angle)?;
+        }
+        Some(())
+    }
+}
+
+/// What a clip does once its last frame is over.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PlaybackMode {
+    /// Start over from the first frame.
+    #[default]
+    Loop,
+    /// Stop on the last frame.
+    Once,
+    /// Play backwards down to the first frame, then forwards again. The first and last
+    /// frame are shown once per turn, not twice.
+    PingPong,
+}
+
+/// A sequence of frames played on a sprite, see [`Animations::add_clip`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+    frames: Vec<Frame>,
+    mode: PlaybackMode,
+    /// Seconds until the clip is back at the frame it started from.
+    cycle_duration: f32,
+}
+
+impl Clip {
+    /// Panics without frames or with a frame that isn't shown for any time, those would
+    /// never let the clip end.
+    pub fn new(frames: Vec<Frame>, mode: PlaybackMode) -> Self {
+        assert!(!frames.is_empty(), "Clip without frames");
+        assert!(
+            frames.iter().all(|frame| frame.duration > 0.0),
+            "Clip frames need a positive duration"
+        );
+
+        let mut clip = Self {
+            frames,
+            mode,
+            cycle_duration: 0.0,
+        };
+        clip.cycle_duration = (0..clip.cycle_len())
+            .map(|step| clip.frames[clip.frame_at(step)].duration)
+            .sum();
+
+        clip
+    }
+
+    /// Frames from source rectangles (top left corner and size) that are all shown for
+    /// `frame_duration` seconds.
+    pub fn uniform<I>(rects: I, frame_duration: f32, mode: PlaybackMode) -> Self
+    where
+        I: IntoIterator<Item = (ultraviolet::Vec2, ultraviolet::Vec2)>,
+    {
+        Self::new(
+            rects
+                .into_iter()
+                .map(|(position, size)| Frame::new(position, size, frame_duration))
+                .collect(),
+            mode,
+        )
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    /// Steps until the clip is back at its first frame, a ping-pong turn visits the
+    /// frames between the first and the last twice.
+    fn cycle_len(&self) -> usize {
+        match self.mode {
+            PlaybackMode::PingPong if self.frames.len() > 1 => 2 * self.frames.len() - 2,
+            _ => self.frames.len(),
+        }
+    }
+
+    /// Frame shown at `step` of a cycle.
+    fn frame_at(&self, step: usize) -> usize {
+        if step < self.frames.len() {
+            step
+        } else {
+            self.cycle_len() - step
+        }
+    }
+}
+
+/// Refers to a clip added to [`Animations`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClipId(usize);
+
+/// Something that happened to a sprite during [`Animations::advance`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AnimationEvent {
+    /// `frame` of the sprite's clip was shown for its full duration.
+    FrameEnded { sprite: SpriteHandle, frame: usize },
+    /// The sprite's clip played through: a loop or ping-pong turn started over, or a clip
+    /// played once stopped on its last frame.
+    ClipEnded { sprite: SpriteHandle, clip: ClipId },
+}
+
+/// Where a sprite is in its clip.
+#[derive(Copy, Clone, Debug)]
+struct Playback {
+    clip: ClipId,
+    /// Position in the cycle of the clip, not the frame index, see [`Clip::frame_at`].
+    step: usize,
+    /// Seconds the current frame has been shown, scaled by the speed.
+    elapsed: f32,
+    speed: f32,
+    finished: bool,
+}
+
+/// Plays clips on sprites by switching their source rectangles as time passes.
+///
+/// Clips are added once and can be played on any number of sprites, each with its own
+/// position and speed. Call [`Animations::advance`] every tick with the time since the
+/// last one.
+#[derive(Default)]
+pub struct Animations {
+    clips: Vec<Clip>,
+    playing: HashMap<SpriteHandle, Playback>,
+    events: Vec<AnimationEvent>,
+}
+
+impl Animations {
+    pub fn new() -> Self {
+        Self {
+            clips: vec![],
+            playing: HashMap::new(),
+            events: vec![],
+        }
+    }
+
+    pub fn add_clip(&mut self, clip: Clip) -> ClipId {
+        self.clips.push(clip);
+        ClipId(self.clips.len() - 1)
+    }
+    pub fn clip(&self, clip: ClipId) -> Option<&Clip> {
+        self.clips.get(clip.0)
+    }
+
+    /// Plays `clip` on `sprite` from its first frame at normal speed, replacing whatever
+    /// it played before. `None` if the sprite was removed or the clip doesn't exist.
+    pub fn play(
+        &mut self,
+        sprites: &mut Sprites,
+        sprite: SpriteHandle,
+        clip: ClipId,
+    ) -> Option<()> {
+        self.clips.get(clip.0)?.frames[0].apply(sprites, sprite)?;
+
+        self.playing.insert(
+            sprite,
+            Playback {
+                clip,
+                step: 0,
+                elapsed: 0.0,
+                speed: 1.0,
+                finished: false,
+            },
+        );
+        Some(())
+    }
+
+    /// Leaves the sprite on its current frame.
+    pub fn stop(&mut self, sprite: SpriteHandle) -> Option<()> {
+        self.playing.remove(&sprite).map(|_| ())
+    }
+
+    pub fn is_playing(&self, sprite: SpriteHandle) -> bool {
+        self.playing.contains_key(&sprite)
+    }
+
+    /// Playback speed of a sprite, 2.0 plays twice as fast and 0.0 pauses it. Negative
+    /// speeds are treated as 0.0.
+    pub fn set_speed(&mut self, sprite: SpriteHandle, val: f32) -> Option<()> {
+        self.playing.get_mut(&sprite)?.speed = val.max(0.0);
+        Some(())
+    }
+    pub fn speed(&self, sprite: SpriteHandle) -> Option<f32> {
+        Some(self.playing.get(&sprite)?.speed)
+    }
+
+    pub fn current_clip(&self, sprite: SpriteHandle) -> Option<ClipId> {
+        Some(self.playing.get(&sprite)?.clip)
+    }
+    /// Index of the frame the sprite shows in its clip.
+    pub fn frame(&self, sprite: SpriteHandle) -> Option<usize> {
+        let playback = self.playing.get(&sprite)?;
+        Some(self.clips[playback.clip.0].frame_at(playback.step))
+    }
+    /// Whether a clip played once has reached its end.
+    pub fn is_finished(&self, sprite: SpriteHandle) -> Option<bool> {
+        Some(self.playing.get(&sprite)?.finished)
+    }
+
+    /// Moves every playing sprite `delta` seconds further, updating the source
+    /// rectangles of those that reach a new frame. Sprites removed from `sprites` since
+    /// are forgotten.
+    ///
+    /// A long `delta` can skip frames, each of them still reports its events. Whole loop
+    /// or ping-pong turns it skips are left out, they report a single
+    /// [`AnimationEvent::ClipEnded`]. Deltas that are infinite or NaN are ignored.
+    pub fn advance(&mut self, sprites: &mut Sprites, delta: f32) {
+        self.events.clear();
+
+        let clips = &self.clips;
+        let events = &mut self.events;
+        self.playing.retain(|sprite, playback| {
+            let clip = &clips[playback.clip.0];
+            let step = playback.step;
+            advance_playback(*sprite, playback, clip, delta, events);
+            if playback.step == step {
+                return sprites.contains(*sprite);
+            }
+
+            clip.frames[clip.frame_at(playback.step)]
+                .apply(sprites, *sprite)
+                .is_some()
+        });
+    }
+
+    /// Events of the last [`Animations::advance`], in order for each sprite.
+    pub fn events(&self) -> &[AnimationEvent] {
+        &self.events
+    }
+}
+
+/// Moves `playback` through `clip` by `delta` seconds, frame by frame.
+fn advance_playback(
+    sprite: SpriteHandle,
+    playback: &mut Playback,
+    clip: &Clip,
+    delta: f32,
+    events: &mut Vec<AnimationEvent>,
+) {
+    let elapsed = playback.elapsed + delta * playback.speed;
+    if playback.finished || !elapsed.is_finite() {
+        return;
+    }
+
+    playback.elapsed = elapsed;
+    // A whole turn ends on the frame it started from, skip those instead of stepping
+    // through them. Stepping alone never ends once subtracting a frame's duration
+    // doesn't change `elapsed` anymore
+    if clip.mode != PlaybackMode::Once && playback.elapsed >= clip.cycle_duration {
+        playback.elapsed %= clip.cycle_duration;
+        events.push(AnimationEvent::ClipEnded {
+            sprite,
+            clip: playback.clip,
+        });
+    }
+    loop {
+        let frame = clip.frame_at(playback.step);
+        let duration = clip.frames[frame].duration;
+        if playback.elapsed < duration {
+            return;
+        }
+
+        playback.elapsed -= duration;
+        events.push(AnimationEvent::FrameEnded { sprite, frame });
+
+        if playback.step + 1 < clip.cycle_len() {
+            playback.step += 1;
+        } else if clip.mode == PlaybackMode::Once {
+            playback.finished = true;
+            playback.elapsed = 0.0;
+            events.push(AnimationEvent::ClipEnded {
+                sprite,
+                clip: playback.clip,
+            });
+            return;
+        } else {
+            playback.step = 0;
+            events.push(AnimationEvent::ClipEnded {
+                sprite,
+                clip: playback.clip,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::sprite::SpriteDesc;
+
+    fn strip(frames: usize, mode: PlaybackMode) -> Clip {
+        Clip::uniform(
+            (0..frames).map(|frame| {
+                (
+                    ultraviolet::Vec2::new(16.0 * frame as f32, 0.0),
+                    ultraviolet::Vec2::new(16.0, 16.0),
+                )
+            }),
+            0.1,
+            mode,
+        )
+    }
+
+    /// Frame shown after each of `ticks` steps of a tenth of a second.
+    fn frames(mode: PlaybackMode, ticks: usize) -> Vec<usize> {
+        let mut sprites = Sprites::new();
+        let sprite = sprites.add_desc(SpriteDesc::new((64, 16)));
+        let mut animations = Animations::new();
+        let clip = animations.add_clip(strip(4, mode));
+        animations.play(&mut sprites, sprite, clip).unwrap();
+
+        (0..ticks)
+            .map(|_| {
+                animations.advance(&mut sprites, 0.1001);
+                animations.frame(sprite).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn playback_modes_step_through_the_frames() {
+        assert_eq!(frames(PlaybackMode::Loop, 6), vec![1, 2, 3, 0, 1, 2]);
+        assert_eq!(frames(PlaybackMode::Once, 6), vec![1, 2, 3, 3, 3, 3]);
+        assert_eq!(
+            frames(PlaybackMode::PingPong, 8),
+            vec![1, 2, 3, 2, 1, 0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn advancing_updates_the_source_rect_and_reports_events() {
+        let mut sprites = Sprites::new();
+        let sprite = sprites.add_desc(SpriteDesc::new((64, 16)));
+        let mut animations = Animations::new();
+        let clip = animations.add_clip(strip(2, PlaybackMode::Once));
+        animations.play(&mut sprites, sprite, clip).unwrap();
+        assert_eq!(
+            sprites.source_size(sprite),
+            Some(ultraviolet::Vec2::new(16.0, 16.0))
+        );
+
+        // Twice the speed covers both frames in a tenth of a second
+        animations.set_speed(sprite, 2.0).unwrap();
+        animations.advance(&mut sprites, 0.1001);
+        assert_eq!(
+            animations.events(),
+            &[
+                AnimationEvent::FrameEnded { sprite, frame: 0 },
+                AnimationEvent::FrameEnded { sprite, frame: 1 },
+                AnimationEvent::ClipEnded { sprite, clip },
+            ]
+        );
+        assert_eq!(animations.is_finished(sprite), Some(true));
+        assert_eq!(
+            sprites.source_position(sprite),
+            Some(ultraviolet::Vec2::new(16.0, 0.0))
+        );
+
+        // Removed sprites are dropped on the next advance
+        sprites.remove(sprite).unwrap();
+        animations.advance(&mut sprites, 0.1);
+        assert!(!animations.is_playing(sprite));
+    }
+
+    #[test]
+    fn huge_and_non_finite_deltas_dont_hang() {
+        for mode in [PlaybackMode::Loop, PlaybackMode::PingPong].iter() {
+            let mut sprites = Sprites::new();
+            let sprite = sprites.add_desc(SpriteDesc::new((64, 16)));
+            let mut animations = Animations::new();
+            let clip = animations.add_clip(strip(4, *mode));
+            animations.play(&mut sprites, sprite, clip).unwrap();
+            animations.advance(&mut sprites, 0.15);
+
+            // Ignored, the sprite stays on its frame
+            for delta in [f32::INFINITY, f32::NAN].iter() {
+                animations.advance(&mut sprites, *delta);
+                assert!(animations.events().is_empty());
+                assert_eq!(animations.frame(sprite), Some(1));
+            }
+
+            // Whole turns are skipped at once
+            animations.advance(&mut sprites, 1e30);
+            assert_eq!(
+                animations.events().first(),
+                Some(&AnimationEvent::ClipEnded { sprite, clip })
+            );
+            assert!(animations.events().len() < 16);
+
+            // Playback carries on as usual afterwards
+            animations.set_speed(sprite, f32::MAX).unwrap();
+            animations.advance(&mut sprites, 1.0);
+            animations.set_speed(sprite, 1.0).unwrap();
+            let frame = animations.frame(sprite).unwrap();
+            animations.advance(&mut sprites, 0.1001);
+            assert_ne!(animations.frame(sprite), Some(frame));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+/// One sprite of the instanced path, expanded into a quad by `sprite_instanced.vert`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpriteInstance {
+    pub(crate) position: [f32; 2],
+    pub(crate) scale: [f32; 2],
+    pub(crate) origin: [f32; 2],
+    /// Size of the source rectangle in texels, the size of the quad before scaling.
+    pub(crate) source_size: [f32; 2],
+    /// Source rectangle in texture coordinates, minimum followed by size.
+    pub(crate) tex_rect: [f32; 4],
+    pub(crate) color: [f32; 4],
+    pub(crate) angle: f32,
+    pub(crate) depth: f32,
+    /// In degrees along x and y.
+    pub(crate) skew: [f32; 2],
+    /// `1.0` along flipped axes, `0.0` otherwise.
+    pub(crate) flip: [f32; 2],
+    /// The sprite's custom [`crate::backend::sprite::Affine2`], only read when
+    /// `custom_transform` is `1.0`.
+    pub(crate) custom_x_axis: [f32; 2],
+    pub(crate) custom_y_axis: [f32; 2],
+    pub(crate) custom_translation: [f32; 2],
+    pub(crate) custom_transform: f32,
+    /// Only used to batch draw calls, the shader never reads it.
+    pub(crate) texture_index: u32,
+}
+
+impl SpriteInstance {
+    pub(crate) fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 18]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 22]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 24]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 26]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 28]>() as wgpu::BufferAddress,
+                    shader_location: 13,
+                    format: wgpu::VertexFormat::Float,
+                },
+            ],
+        }
+    }
+}
@@ -1,7 +1,11 @@
+pub mod animation;
+pub mod instance;
+pub mod parallelism;
 pub mod pipeline;
 pub mod renderer;
 pub mod resource;
 pub mod shader;
+pub mod sheet;
 pub mod sprite;
 pub mod swapchain;
 pub mod target;
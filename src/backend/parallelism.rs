@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+/// Sprites per chunk are never fewer than this, smaller chunks cost more to schedule
+/// than to run.
+const MIN_CHUNK_SPRITES: usize = 256;
+
+/// Chunks per thread, a few so threads that finish early can steal from the others.
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// How sprite work like vertex generation and sorting is spread over threads.
+#[derive(Clone, Debug)]
+pub struct Parallelism {
+    /// Fewer sprites than this are handled on the calling thread, handing them to a
+    /// pool would take longer than the work itself.
+    pub sequential_threshold: usize,
+    /// Largest number of sprites one task handles at a time. The default keeps the
+    /// vertices of a chunk (144 bytes per sprite) within a typical 256 KiB L2 cache.
+    pub max_chunk_sprites: usize,
+    /// Pool to run on, rayon's global pool if `None`. Give the renderer its own pool so
+    /// it doesn't compete with the game's workers.
+    pub pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl Parallelism {
+    /// Never leaves the calling thread.
+    pub fn sequential() -> Self {
+        Self {
+            sequential_threshold: usize::MAX,
+            ..Self::default()
+        }
+    }
+
+    /// Runs on `pool` instead of rayon's global pool.
+    pub fn with_pool(pool: Arc<rayon::ThreadPool>) -> Self {
+        Self {
+            pool: Some(pool),
+            ..Self::default()
+        }
+    }
+
+    /// Runs on a pool of its own with a thread per physical core, out of the way of
+    /// everything on rayon's global pool.
+    pub fn dedicated() -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get_physical())
+            .thread_name(|index| format!("sprites-{}", index))
+            .build()
+            .expect("Unable to create the sprite thread pool");
+
+        Self::with_pool(Arc::new(pool))
+    }
+
+    /// Whether `sprite_count` sprites are worth splitting up.
+    pub fn is_parallel(&self, sprite_count: usize) -> bool {
+        sprite_count >= self.sequential_threshold
+    }
+
+    /// Threads of the pool the work runs on.
+    pub fn thread_count(&self) -> usize {
+        match &self.pool {
+            Some(pool) => pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        }
+    }
+
+    /// Sprites per chunk when splitting up `sprite_count` sprites.
+    pub fn chunk_sprites(&self, sprite_count: usize) -> usize {
+        let chunk_sprites = sprite_count / (self.thread_count() * CHUNKS_PER_THREAD);
+        chunk_sprites
+            .min(self.max_chunk_sprites)
+            .max(MIN_CHUNK_SPRITES)
+    }
+
+    /// Runs `op` on the configured pool, so rayon's parallel iterators inside it use that
+    /// pool too.
+    pub fn install<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self {
+            sequential_threshold: 2048,
+            max_chunk_sprites: 1792,
+            pool: None,
+        }
+    }
+}
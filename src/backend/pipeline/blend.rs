@@ -0,0 +1,164 @@
+/// How the fragments of a pipeline are combined with what is already in the target.
+///
+/// The blend factors expect premultiplied color out of the fragment shader, see
+/// [`BlendMode::premultiplies_in_shader`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Overwrite the target, transparent pixels come out black.
+    Replace,
+    /// Straight (non-premultiplied) alpha, what most PNGs like `ferris.png` contain.
+    #[default]
+    Alpha,
+    /// Alpha for textures and colors whose RGB is already multiplied by their alpha.
+    PremultipliedAlpha,
+    /// Adds the color weighted by its alpha, for glows, sparks and lasers.
+    Additive,
+    /// Multiplies the target by the color, only ever darkens.
+    Multiply,
+    /// Inverse of multiply, only ever brightens.
+    Screen,
+}
+
+impl BlendMode {
+    /// Whether the fragment shader multiplies the color by its alpha before blending.
+    ///
+    /// Multiply and screen can't be expressed with blend factors on straight alpha, so
+    /// every mode blends premultiplied color. Only `PremultipliedAlpha` textures already
+    /// come that way.
+    pub fn premultiplies_in_shader(&self) -> bool {
+        *self != BlendMode::PremultipliedAlpha
+    }
+
+    pub fn color_blend(&self) -> wgpu::BlendState {
+        let (src_factor, dst_factor) = match self {
+            BlendMode::Replace => (wgpu::BlendFactor::One, wgpu::BlendFactor::Zero),
+            BlendMode::Alpha | BlendMode::PremultipliedAlpha => {
+                (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha)
+            }
+            BlendMode::Additive => (wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+            // Transparent texels are black after premultiplying, so the target is
+            // kept where the sprite doesn't cover it
+            BlendMode::Multiply => (
+                wgpu::BlendFactor::DstColor,
+                wgpu::BlendFactor::OneMinusSrcAlpha,
+            ),
+            BlendMode::Screen => (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcColor),
+        };
+
+        wgpu::BlendState {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+
+    pub fn alpha_blend(&self) -> wgpu::BlendState {
+        let (src_factor, dst_factor) = match self {
+            BlendMode::Replace => (wgpu::BlendFactor::One, wgpu::BlendFactor::Zero),
+            BlendMode::Alpha | BlendMode::PremultipliedAlpha => {
+                (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha)
+            }
+            // These only tint what is already there, so they keep its coverage
+            BlendMode::Additive | BlendMode::Multiply | BlendMode::Screen => {
+                (wgpu::BlendFactor::Zero, wgpu::BlendFactor::One)
+            }
+        };
+
+        wgpu::BlendState {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+
+    /// Whether sprites have to be drawn back to front to composite correctly.
+    ///
+    /// Alpha blending is not commutative: a translucent sprite drawn before the ones
+    /// behind it hides them. Additive, multiply and screen look the same in any order,
+    /// and opaque (replace) sprites can rely on a depth buffer instead.
+    pub fn needs_sorting(&self) -> bool {
+        match self {
+            BlendMode::Alpha | BlendMode::PremultipliedAlpha => true,
+            BlendMode::Replace | BlendMode::Additive | BlendMode::Multiply | BlendMode::Screen => {
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [BlendMode; 6] = [
+        BlendMode::Replace,
+        BlendMode::Alpha,
+        BlendMode::PremultipliedAlpha,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+
+    fn factor(factor: wgpu::BlendFactor, src: [f32; 4], dst: [f32; 4], channel: usize) -> f32 {
+        match factor {
+            wgpu::BlendFactor::Zero => 0.0,
+            wgpu::BlendFactor::One => 1.0,
+            wgpu::BlendFactor::SrcColor => src[channel],
+            wgpu::BlendFactor::OneMinusSrcColor => 1.0 - src[channel],
+            wgpu::BlendFactor::SrcAlpha => src[3],
+            wgpu::BlendFactor::OneMinusSrcAlpha => 1.0 - src[3],
+            wgpu::BlendFactor::DstColor => dst[channel],
+            wgpu::BlendFactor::OneMinusDstColor => 1.0 - dst[channel],
+            wgpu::BlendFactor::DstAlpha => dst[3],
+            wgpu::BlendFactor::OneMinusDstAlpha => 1.0 - dst[3],
+            other => panic!("{:?} is not used by any blend mode", other),
+        }
+    }
+
+    /// What the blend stage writes for a straight alpha `texel` drawn over `dst`. The
+    /// shader premultiplies it, or the texture already is for `PremultipliedAlpha`.
+    fn blend(mode: BlendMode, texel: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+        let mut src = texel;
+        for channel in 0..3 {
+            src[channel] *= src[3];
+        }
+
+        let mut out = [0.0; 4];
+        for channel in 0..4 {
+            let state = if channel < 3 {
+                mode.color_blend()
+            } else {
+                mode.alpha_blend()
+            };
+            assert_eq!(state.operation, wgpu::BlendOperation::Add);
+            out[channel] = src[channel] * factor(state.src_factor, src, dst, channel)
+                + dst[channel] * factor(state.dst_factor, src, dst, channel);
+        }
+        out
+    }
+
+    #[test]
+    fn transparent_texels_leave_the_target_alone() {
+        let dst = [0.2, 0.5, 0.8, 1.0];
+        for &mode in MODES.iter().filter(|&&mode| mode != BlendMode::Replace) {
+            assert_eq!(blend(mode, [0.9, 0.4, 0.1, 0.0], dst), dst, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn opaque_texels_blend_by_mode() {
+        let texel = [0.5, 0.25, 1.0, 1.0];
+        let dst = [0.5, 1.0, 0.5, 1.0];
+        assert_eq!(blend(BlendMode::Replace, texel, dst), texel);
+        assert_eq!(blend(BlendMode::Alpha, texel, dst), texel);
+        assert_eq!(
+            blend(BlendMode::Additive, texel, dst),
+            [1.0, 1.25, 1.5, 1.0]
+        );
+        assert_eq!(
+            blend(BlendMode::Multiply, texel, dst),
+            [0.25, 0.25, 0.5, 1.0]
+        );
+        assert_eq!(blend(BlendMode::Screen, texel, dst), [0.75, 1.0, 1.0, 1.0]);
+    }
+}
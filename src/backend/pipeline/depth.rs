@@ -0,0 +1,55 @@
+use crate::backend::pipeline::blend::BlendMode;
+use crate::backend::resource::texture::Texture;
+
+/// How a pipeline decides which of two overlapping sprites ends up in front.
+///
+/// Greater depth is closer to the viewer, the visible range is `-100.0..=100.0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DepthMode {
+    /// Ignore depth and draw in storage order. With several textures there is one batch
+    /// per texture, drawn by texture index, so storage order only holds within a texture.
+    Disabled,
+    /// Test against and write to the renderer's depth buffer. Meant for opaque sprites,
+    /// fragments below the alpha cutoff are discarded so the transparent parts of a
+    /// sprite don't hide what is behind them.
+    Buffer,
+    /// Sort sprites back to front on the CPU whenever they change. Meant for translucent
+    /// sprites, which can't write depth without cutting holes into each other.
+    Sorted,
+}
+
+impl DepthMode {
+    /// Sorting for blend modes that need it, the depth buffer for everything else.
+    pub fn for_blend_mode(blend_mode: BlendMode) -> Self {
+        if blend_mode.needs_sorting() {
+            DepthMode::Sorted
+        } else {
+            DepthMode::Buffer
+        }
+    }
+
+    /// Depth state for a pipeline drawing into the renderer's depth buffer.
+    pub fn depth_stencil(&self) -> wgpu::DepthStencilState {
+        let (depth_write_enabled, depth_compare) = match self {
+            DepthMode::Buffer => (true, wgpu::CompareFunction::LessEqual),
+            DepthMode::Disabled | DepthMode::Sorted => (false, wgpu::CompareFunction::Always),
+        };
+
+        wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled,
+            depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+            clamp_depth: false,
+        }
+    }
+
+    /// Fragments with a lower alpha are discarded.
+    pub fn alpha_cutoff(&self) -> f32 {
+        match self {
+            DepthMode::Buffer => 0.5,
+            DepthMode::Disabled | DepthMode::Sorted => 0.0,
+        }
+    }
+}
@@ -1,5 +1,8 @@
+pub mod blend;
+pub mod depth;
 pub mod sprite;
 pub mod texture;
+pub mod transform;
 
 
 
@@ -15,6 +18,10 @@ pub trait Pipeline {
         bind_group_builder: F0,
     );
     fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32);
+    /// Called by the renderer before every frame. Upload whatever changed since the last frame.
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue);
+    /// Record the draw calls of this pipeline into a render pass of the renderer.
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>);
     fn layout(&self) -> &Option<wgpu::PipelineLayout>;
     fn pipeline(&self) -> &Option<wgpu::RenderPipeline>;
     fn vertex_buffer(&self) -> &Option<wgpu::Buffer>;
@@ -1,34 +1,64 @@
+use crate::backend::instance::SpriteInstance;
+use crate::backend::pipeline::blend::BlendMode;
+use crate::backend::pipeline::depth::DepthMode;
+use crate::backend::pipeline::transform::TransformMode;
 use crate::backend::pipeline::Pipeline;
 use crate::backend::resource::build_bind_group;
 use crate::backend::resource::uniform::Uniform;
 use crate::backend::shader::ShaderSet;
-use crate::backend::sprite::Sprites;
+use crate::backend::sprite::{
+    index_format_for, index_size, Indices, SpriteBatch, SpriteDesc, Sprites,
+};
 use crate::backend::vertex::Vertex;
-use wgpu::util::DeviceExt;
+use bytemuck::Zeroable;
+use std::ops::Range;
 use wgpu::{BindGroup, PipelineLayout, RenderPipeline};
 
 // TEXTURE RANGE: (0.0) - (1.0)
 // FRAME RANGE: (-1.0) - (1.0)
 
+/// Smallest number of sprites the vertex and index buffers get room for.
+const MIN_SPRITE_CAPACITY: usize = 64;
+
+/// Buffer capacity (in sprites) for `count` sprites, rounded up to the next power of two.
+fn sprite_capacity_for(count: usize) -> usize {
+    count.max(MIN_SPRITE_CAPACITY).next_power_of_two()
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Global {
     pub ortho: ultraviolet::Mat4,
     pub transform: ultraviolet::Mat4,
+    pub alpha_cutoff: f32,
+    /// `1.0` when the fragment shader has to premultiply the color, see
+    /// [`BlendMode::premultiplies_in_shader`].
+    pub premultiply: f32,
+    pub _padding: [f32; 2],
 }
 
 pub struct SpritePipeline {
     shaders: ShaderSet,
+    instanced_vertex_shader: wgpu::ShaderModule,
     texture_format: wgpu::TextureFormat,
     layout: Option<wgpu::PipelineLayout>,
     pipeline: Option<wgpu::RenderPipeline>,
+    blend_mode: BlendMode,
+    depth_mode: DepthMode,
+    transform_mode: TransformMode,
     vertex_buffer: Option<wgpu::Buffer>,
     index_buffer: Option<wgpu::Buffer>,
+    index_format: wgpu::IndexFormat,
+    /// Reused for every vertex upload, so regenerating vertices doesn't allocate.
+    vertex_staging: Vec<Vertex>,
+    sprite_capacity: usize,
+    indexed_sprites: usize,
     global_buffer: Option<Uniform<Global>>,
+    ortho: ultraviolet::Mat4,
     bind_groups: Option<Vec<wgpu::BindGroup>>,
     sprites: Sprites,
-    texture_width: u32,
-    texture_height: u32,
+    batches: Vec<SpriteBatch>,
+    texture_sizes: Vec<(u32, u32)>,
 }
 
 impl SpritePipeline {
@@ -38,6 +68,30 @@ impl SpritePipeline {
         texture_width: u32,
         texture_height: u32,
     ) -> Self {
+        Self::with_texture_sizes(
+            device,
+            texture_format,
+            vec![(texture_width, texture_height)],
+        )
+    }
+
+    /// Pipeline drawing from several textures. Every bind group returned by the bind group
+    /// builder is one texture (and its sampler) in set 1, sprites pick theirs with their
+    /// texture index. `texture_sizes` lists the size of each of them in the same order.
+    /// Sprites with a texture index past the last texture make the upload panic.
+    ///
+    /// Sprites are drawn in one batch per texture, or per run of sprites sharing a texture
+    /// when they are sorted by depth.
+    pub fn with_texture_sizes(
+        device: &wgpu::Device,
+        texture_format: wgpu::TextureFormat,
+        texture_sizes: Vec<(u32, u32)>,
+    ) -> Self {
+        assert!(
+            !texture_sizes.is_empty(),
+            "Sprite pipeline needs at least one texture"
+        );
+
         // Shaders
         let vs_module =
             device.create_shader_module(&wgpu::include_spirv!("../../shaders/sprite.vert.spv"));
@@ -47,54 +101,38 @@ impl SpritePipeline {
             vertex: vs_module,
             fragment: fs_module,
         };
+        let instanced_vs_module = device.create_shader_module(&wgpu::include_spirv!(
+            "../../shaders/sprite_instanced.vert.spv"
+        ));
 
         let mut sprites = Sprites::new();
-        sprites.add(
-            0,
-            ultraviolet::Vec2::new(0.0, 0.0),
-            ultraviolet::Vec2::new(90.0, 90.0),
-            ultraviolet::Vec2::new(180.0, 180.0),
-            0.0,
-            ultraviolet::Vec2::new(1.0, 1.0),
-            1.0,
-            [1.0, 1.0, 1.0, 1.0],
-            ultraviolet::Vec2::new(0.0, 0.0),
-        );
-        sprites.add(
-            0,
-            ultraviolet::Vec2::new(0.0, 0.0),
-            ultraviolet::Vec2::new(90.0, 90.0),
-            ultraviolet::Vec2::new(90.0, 90.0),
-            0.0,
-            ultraviolet::Vec2::new(1.0, 1.0),
-            1.0,
-            [1.0, 1.0, 1.0, 1.0],
-            ultraviolet::Vec2::new(0.0, 0.0),
-        );
-        sprites.add(
-            0,
-            ultraviolet::Vec2::new(0.0, 0.0),
-            ultraviolet::Vec2::new(90.0, 90.0),
-            ultraviolet::Vec2::new(0.0, 0.0),
-            0.0,
-            ultraviolet::Vec2::new(1.0, 1.0),
-            1.0,
-            [1.0, 1.0, 1.0, 1.0],
-            ultraviolet::Vec2::new(0.0, 0.0),
-        );
+        sprites.extend([180.0, 90.0, 0.0].iter().map(|offset| {
+            SpriteDesc::new((90, 90))
+                .with_position(ultraviolet::Vec2::new(*offset, *offset))
+                .with_depth(1.0)
+        }));
 
         SpritePipeline {
             shaders: shader_set,
+            instanced_vertex_shader: instanced_vs_module,
             texture_format,
             layout: None,
             pipeline: None,
+            blend_mode: BlendMode::default(),
+            depth_mode: DepthMode::for_blend_mode(BlendMode::default()),
+            transform_mode: TransformMode::default(),
             sprites,
             vertex_buffer: None,
             index_buffer: None,
+            index_format: wgpu::IndexFormat::Uint16,
+            vertex_staging: vec![],
+            sprite_capacity: 0,
+            indexed_sprites: 0,
             global_buffer: None,
+            ortho: ultraviolet::Mat4::identity(),
             bind_groups: None,
-            texture_width,
-            texture_height,
+            batches: vec![],
+            texture_sizes,
         }
     }
 
@@ -102,81 +140,314 @@ impl SpritePipeline {
         &mut self.sprites
     }
 
-    pub fn vertices_indices(&self) -> (Vec<Vertex>, Vec<u16>) {
+    pub fn vertices_indices(&self) -> (Vec<Vertex>, Indices) {
         self.sprites
-            .vertices_indices(self.texture_width, self.texture_height)
+            .vertices_indices(&self.texture_sizes, self.index_format)
     }
-}
 
-impl Pipeline for SpritePipeline {
-    fn initialize<
-        F0: Fn(&wgpu::Device, &wgpu::Queue) -> Vec<(wgpu::BindGroupLayout, wgpu::BindGroup)>,
-    >(
+    /// Number of sprites the current vertex and index buffers can hold.
+    pub fn sprite_capacity(&self) -> usize {
+        self.sprite_capacity
+    }
+
+    /// Format of the current index buffer. It switches to 32-bit indices once the buffers
+    /// grow past [`crate::backend::sprite::MAX_U16_SPRITES`] and back when they shrink.
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        self.index_format
+    }
+
+    /// Shrinks the vertex and index buffers down to the smallest capacity that still
+    /// fits every sprite, then re-uploads the sprites into them.
+    pub fn shrink_to_fit(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let capacity = sprite_capacity_for(self.sprites.len());
+        if self.vertex_buffer.is_some() && capacity < self.sprite_capacity {
+            self.create_buffers(device, capacity);
+            self.upload_sprites(device, queue);
+        }
+    }
+
+    fn create_buffers(&mut self, device: &wgpu::Device, capacity: usize) {
+        let index_format = index_format_for(capacity);
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Vertex Buffer"),
+            size: (capacity * self.transform_mode.sprite_size()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Instances are drawn as six vertices each, without indices
+        let index_buffer = match self.transform_mode {
+            TransformMode::Cpu => Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Index Buffer"),
+                size: (capacity * 6 * index_size(index_format)) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsage::INDEX | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            })),
+            TransformMode::Instanced => None,
+        };
+
+        self.vertex_buffer = Some(vertex_buffer);
+        self.index_buffer = index_buffer;
+        self.index_format = index_format;
+        self.sprite_capacity = capacity;
+        self.indexed_sprites = 0;
+    }
+
+    /// Uploads every sprite, growing the buffers first if the sprites outgrew them.
+    fn upload_sprites(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.sprites.len() > self.sprite_capacity {
+            self.create_buffers(device, sprite_capacity_for(self.sprites.len()));
+        }
+
+        match self.transform_mode {
+            TransformMode::Cpu => {
+                self.sprites.update_transforms();
+                self.upload_vertices(queue, 0..self.sprites.len());
+                self.upload_indices(queue);
+            }
+            TransformMode::Instanced => self.upload_instances(queue),
+        }
+        self.sprites.clear_dirty();
+    }
+
+    /// Regenerates and uploads only the quads of sprites that changed since the last upload.
+    fn upload_dirty_sprites(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.sprites.len() > self.sprite_capacity {
+            // New buffers start out empty, so everything has to go up
+            self.upload_sprites(device, queue);
+            return;
+        }
+
+        match self.transform_mode {
+            TransformMode::Cpu => {
+                self.sprites.update_transforms();
+                for range in self.sprites.dirty_ranges() {
+                    self.upload_vertices(queue, range);
+                }
+
+                self.upload_indices(queue);
+            }
+            TransformMode::Instanced if self.uses_storage_order() => {
+                let sprite_size = self.transform_mode.sprite_size();
+                for range in self.sprites.dirty_ranges() {
+                    let offset = (range.start * sprite_size) as wgpu::BufferAddress;
+                    let instances = self.sprites.instances(range, &self.texture_sizes);
+                    queue.write_buffer(
+                        self.vertex_buffer.as_ref().unwrap(),
+                        offset,
+                        bytemuck::cast_slice(&instances),
+                    );
+                }
+                self.batches = self.storage_order_batches();
+            }
+            // Instances are stored in draw order, so any change can move all of them
+            TransformMode::Instanced => self.upload_instances(queue),
+        }
+        self.sprites.clear_dirty();
+    }
+
+    /// Regenerates the vertices of the sprites in `range` into the staging vertices and
+    /// uploads them to their place in the vertex buffer.
+    fn upload_vertices(&mut self, queue: &wgpu::Queue, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let offset = (range.start * self.transform_mode.sprite_size()) as wgpu::BufferAddress;
+        self.vertex_staging
+            .resize(range.len() * 4, Vertex::zeroed());
+        self.sprites
+            .write_vertices(range, &self.texture_sizes, &mut self.vertex_staging);
+        queue.write_buffer(
+            self.vertex_buffer.as_ref().unwrap(),
+            offset,
+            bytemuck::cast_slice(&self.vertex_staging),
+        );
+    }
+
+    /// Uploads the instances of every sprite in draw order.
+    fn upload_instances(&mut self, queue: &wgpu::Queue) {
+        let instances = if self.uses_storage_order() {
+            self.batches = self.storage_order_batches();
+            self.sprites
+                .instances(0..self.sprites.len(), &self.texture_sizes)
+        } else {
+            let order = self.draw_order();
+            self.batches = self.sprites.batches(&order);
+            self.sprites.ordered_instances(&order, &self.texture_sizes)
+        };
+
+        if !instances.is_empty() {
+            queue.write_buffer(
+                self.vertex_buffer.as_ref().unwrap(),
+                0,
+                bytemuck::cast_slice(&instances),
+            );
+        }
+    }
+
+    /// With a single texture and no sorting, sprites are drawn in storage order in one batch.
+    fn uses_storage_order(&self) -> bool {
+        self.texture_sizes.len() == 1 && self.depth_mode != DepthMode::Sorted
+    }
+
+    fn upload_indices(&mut self, queue: &wgpu::Queue) {
+        if !self.uses_storage_order() {
+            if self.sprites.is_reordered() || self.sprites.len() != self.indexed_sprites {
+                self.upload_ordered_indices(queue);
+            }
+            return;
+        }
+
+        if self.indexed_sprites < self.sprite_capacity {
+            // Quad indices never change, so the whole buffer is filled once
+            let indices = Sprites::indices(0..self.sprite_capacity, self.index_format);
+            queue.write_buffer(self.index_buffer.as_ref().unwrap(), 0, indices.as_bytes());
+            self.indexed_sprites = self.sprite_capacity;
+        }
+        self.batches = self.storage_order_batches();
+    }
+
+    fn storage_order_batches(&self) -> Vec<SpriteBatch> {
+        vec![SpriteBatch {
+            texture_index: 0,
+            sprites: 0..self.sprites.len(),
+        }]
+    }
+
+    /// Back to front when sorting, otherwise grouped by texture.
+    fn draw_order(&self) -> Vec<usize> {
+        if self.depth_mode == DepthMode::Sorted {
+            self.sprites.back_to_front()
+        } else {
+            self.sprites.by_texture()
+        }
+    }
+
+    /// The draw order follows the index buffer, so sorting by depth or grouping by texture
+    /// only has to rewrite the indices.
+    fn upload_ordered_indices(&mut self, queue: &wgpu::Queue) {
+        let order = self.draw_order();
+
+        let indices = Sprites::ordered_indices(&order, self.index_format);
+        if !indices.is_empty() {
+            queue.write_buffer(self.index_buffer.as_ref().unwrap(), 0, indices.as_bytes());
+        }
+        self.batches = self.sprites.batches(&order);
+        self.indexed_sprites = self.sprites.len();
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Changes how this pipeline blends into the target, rebuilding the render pipeline
+    /// right away if it was already initialized.
+    pub fn set_blend_mode(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        bind_group_builder: F0,
+        blend_mode: BlendMode,
     ) {
-        // Build vertex and index lists
-        let (vertices, indices) = self.vertices_indices();
+        self.blend_mode = blend_mode;
+        if let Some(layout) = &self.layout {
+            self.pipeline = Some(self.create_render_pipeline(device, layout));
+            self.global_buffer
+                .as_ref()
+                .unwrap()
+                .set(queue, self.global());
+        }
+    }
 
-        // Vertex buffer
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
-        });
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsage::INDEX | wgpu::BufferUsage::COPY_DST,
-        });
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
 
-        // Global buffer
-        let global_buffer = Uniform::new(
-            &device,
-            Global {
-                ortho: ultraviolet::Mat4::identity(),
-                transform: ultraviolet::Mat4::identity(),
+    /// Switches between depth buffer testing and CPU sorting, rebuilding the render
+    /// pipeline and the draw order right away if the pipeline was already initialized.
+    pub fn set_depth_mode(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        depth_mode: DepthMode,
+    ) {
+        self.depth_mode = depth_mode;
+        if let Some(layout) = &self.layout {
+            self.pipeline = Some(self.create_render_pipeline(device, layout));
+            self.global_buffer
+                .as_ref()
+                .unwrap()
+                .set(queue, self.global());
+
+            // The draw order depends on the depth mode, rewrite all of it
+            match self.transform_mode {
+                TransformMode::Cpu => {
+                    self.indexed_sprites = 0;
+                    self.upload_indices(queue);
+                }
+                TransformMode::Instanced => self.upload_instances(queue),
+            }
+        }
+    }
+
+    pub fn transform_mode(&self) -> TransformMode {
+        self.transform_mode
+    }
+
+    /// Switches between generating vertices on the CPU and instancing, rebuilding the
+    /// render pipeline and the buffers right away if the pipeline was already initialized.
+    pub fn set_transform_mode(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        transform_mode: TransformMode,
+    ) {
+        self.transform_mode = transform_mode;
+        if let Some(layout) = &self.layout {
+            self.pipeline = Some(self.create_render_pipeline(device, layout));
+            self.create_buffers(device, sprite_capacity_for(self.sprites.len()));
+            self.upload_sprites(device, queue);
+        }
+    }
+
+    fn global(&self) -> Global {
+        Global {
+            ortho: self.ortho,
+            transform: ultraviolet::Mat4::identity(),
+            alpha_cutoff: self.depth_mode.alpha_cutoff(),
+            premultiply: if self.blend_mode.premultiplies_in_shader() {
+                1.0
+            } else {
+                0.0
             },
-        );
+            _padding: [0.0; 2],
+        }
+    }
 
-        // Bind groups
-        let (global_bind_group_layout, global_bind_group) =
-            build_bind_group(&device, wgpu::ShaderStage::VERTEX, vec![&global_buffer]);
-        let (texture_bind_group_layouts, texture_bind_groups): (Vec<_>, Vec<_>) =
-            bind_group_builder(&device, &queue).into_iter().unzip();
-        let bind_group_layouts = vec![global_bind_group_layout]
-            .into_iter()
-            .chain(texture_bind_group_layouts)
-            .collect::<Vec<_>>();
-        let bind_groups = vec![global_bind_group]
-            .into_iter()
-            .chain(texture_bind_groups)
-            .collect::<Vec<_>>();
+    fn create_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+    ) -> wgpu::RenderPipeline {
+        let (vertex_module, vertex_buffers) = match self.transform_mode {
+            TransformMode::Cpu => (&self.shaders.vertex, [Vertex::desc()]),
+            TransformMode::Instanced => (&self.instanced_vertex_shader, [SpriteInstance::desc()]),
+        };
 
-        // Pipeline
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Sprite Pipeline Layout"),
-                bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
-                push_constant_ranges: &[],
-            });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Sprite Pipeline"),
-            layout: Some(&render_pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
-                module: &self.shaders.vertex,
+                module: vertex_module,
                 entry_point: "main",
-                buffers: &[Vertex::desc()],
+                buffers: &vertex_buffers,
             },
             fragment: Some(wgpu::FragmentState {
                 module: &self.shaders.fragment,
                 entry_point: "main",
                 targets: &[wgpu::ColorTargetState {
                     format: self.texture_format,
-                    color_blend: wgpu::BlendState::REPLACE,
-                    alpha_blend: wgpu::BlendState::REPLACE,
+                    color_blend: self.blend_mode.color_blend(),
+                    alpha_blend: self.blend_mode.alpha_blend(),
                     write_mask: wgpu::ColorWrite::ALL,
                 }],
             }),
@@ -187,54 +458,112 @@ impl Pipeline for SpritePipeline {
                 cull_mode: wgpu::CullMode::Back,
                 polygon_mode: wgpu::PolygonMode::Fill,
             },
-            depth_stencil: None,
+            depth_stencil: Some(self.depth_mode.depth_stencil()),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
-        });
+        })
+    }
+}
+
+impl Pipeline for SpritePipeline {
+    fn initialize<
+        F0: Fn(&wgpu::Device, &wgpu::Queue) -> Vec<(wgpu::BindGroupLayout, wgpu::BindGroup)>,
+    >(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_group_builder: F0,
+    ) {
+        // Vertex and index buffers
+        self.create_buffers(device, sprite_capacity_for(self.sprites.len()));
+        self.upload_sprites(device, queue);
+
+        // Global buffer
+        let global_buffer = Uniform::new(&device, self.global());
+
+        // Bind groups
+        let (global_bind_group_layout, global_bind_group) = build_bind_group(
+            &device,
+            wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+            vec![&global_buffer],
+        );
+        let (texture_bind_group_layouts, texture_bind_groups): (Vec<_>, Vec<_>) =
+            bind_group_builder(&device, &queue).into_iter().unzip();
+        assert_eq!(
+            texture_bind_groups.len(),
+            self.texture_sizes.len(),
+            "Sprite pipeline needs a bind group for every texture size"
+        );
+        // Every texture is bound to set 1 in turn, so they all share the first layout
+        let bind_group_layouts = vec![global_bind_group_layout]
+            .into_iter()
+            .chain(texture_bind_group_layouts.into_iter().take(1))
+            .collect::<Vec<_>>();
+        let bind_groups = vec![global_bind_group]
+            .into_iter()
+            .chain(texture_bind_groups)
+            .collect::<Vec<_>>();
+
+        // Pipeline
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Sprite Pipeline Layout"),
+                bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
+                push_constant_ranges: &[],
+            });
+        let render_pipeline = self.create_render_pipeline(device, &render_pipeline_layout);
 
         self.layout = Some(render_pipeline_layout);
         self.pipeline = Some(render_pipeline);
-        self.vertex_buffer = Some(vertex_buffer);
-        self.index_buffer = Some(index_buffer);
         self.global_buffer = Some(global_buffer);
         self.bind_groups = Some(bind_groups);
     }
 
     fn resize(&mut self, _device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32) {
         if let Some(_) = self.vertex_buffer {
-            // Update the vertex buffers
-            let (vertices, indices) = self.vertices_indices();
-
-            // Vertex buffer
-            queue.write_buffer(
-                self.vertex_buffer.as_mut().unwrap(),
-                0,
-                bytemuck::cast_slice(&vertices),
-            );
-            queue.write_buffer(
-                self.index_buffer.as_mut().unwrap(),
-                0,
-                bytemuck::cast_slice(&indices),
-            );
-
             // Global uniform buffer
-            self.global_buffer.as_ref().unwrap().set(
-                &queue,
-                Global {
-                    ortho: ultraviolet::projection::rh_yup::orthographic_wgpu_dx(
-                        0.0,
-                        width as f32,
-                        0.0,
-                        height as f32,
-                        -100.0,
-                        100.0,
-                    ),
-                    transform: ultraviolet::Mat4::identity(),
-                },
+            self.ortho = ultraviolet::projection::rh_yup::orthographic_wgpu_dx(
+                0.0,
+                width as f32,
+                0.0,
+                height as f32,
+                -100.0,
+                100.0,
             );
+            self.global_buffer
+                .as_ref()
+                .unwrap()
+                .set(&queue, self.global());
+        }
+    }
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        // Skip the upload entirely on frames where no sprite changed
+        if self.vertex_buffer.is_some() && (self.sprites.is_dirty() || self.sprites.is_reordered())
+        {
+            self.upload_dirty_sprites(device, queue);
+        }
+    }
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let bind_groups = self.bind_groups.as_ref().unwrap();
+
+        render_pass.set_pipeline(self.pipeline.as_ref().unwrap());
+        render_pass.set_bind_group(0, &bind_groups[0], &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.as_ref().unwrap().slice(..));
+        if let Some(index_buffer) = &self.index_buffer {
+            render_pass.set_index_buffer(index_buffer.slice(..), self.index_format);
+        }
+        for batch in &self.batches {
+            // Every texture index got checked against the texture sizes during the upload
+            render_pass.set_bind_group(1, &bind_groups[1 + batch.texture_index as usize], &[]);
+
+            let (start, end) = (batch.sprites.start as u32, batch.sprites.end as u32);
+            match self.transform_mode {
+                TransformMode::Cpu => render_pass.draw_indexed(start * 6..end * 6, 0, 0..1),
+                TransformMode::Instanced => render_pass.draw(0..6, start..end),
+            }
         }
     }
     fn layout(&self) -> &Option<PipelineLayout> {
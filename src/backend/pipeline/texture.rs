@@ -1,3 +1,5 @@
+use crate::backend::pipeline::blend::BlendMode;
+use crate::backend::pipeline::depth::DepthMode;
 use crate::backend::pipeline::Pipeline;
 use crate::backend::shader::ShaderSet;
 use crate::backend::vertex::Vertex;
@@ -11,18 +13,22 @@ const VERTICES: &[Vertex] = &[
     Vertex {
         position: [-1.0, 1.0, 1.0],
         tex_coords: [0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     }, // A
     Vertex {
         position: [-1.0, -1.0, 1.0],
         tex_coords: [0.0, 1.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     }, // B
     Vertex {
         position: [1.0, -1.0, 1.0],
         tex_coords: [1.0, 1.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     }, // C
     Vertex {
         position: [1.0, 1.0, 1.0],
         tex_coords: [1.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     }, // D
 ];
 
@@ -30,9 +36,13 @@ const INDICES: &[u16] = &[0, 1, 3, 3, 1, 2];
 
 pub struct TexturePipeline {
     shaders: ShaderSet,
+    /// Fragment shader for `BlendMode::PremultipliedAlpha`, the texture is already
+    /// premultiplied so it's passed through as is.
+    premultiplied_fragment_shader: wgpu::ShaderModule,
     texture_format: wgpu::TextureFormat,
     layout: Option<wgpu::PipelineLayout>,
     pipeline: Option<wgpu::RenderPipeline>,
+    blend_mode: BlendMode,
     vertex_buffer: Option<wgpu::Buffer>,
     index_buffer: Option<wgpu::Buffer>,
     bind_groups: Option<Vec<wgpu::BindGroup>>,
@@ -45,6 +55,9 @@ impl TexturePipeline {
             device.create_shader_module(&wgpu::include_spirv!("../../shaders/texture.vert.spv"));
         let fs_module =
             device.create_shader_module(&wgpu::include_spirv!("../../shaders/texture.frag.spv"));
+        let premultiplied_fs_module = device.create_shader_module(&wgpu::include_spirv!(
+            "../../shaders/texture_premultiplied.frag.spv"
+        ));
         let shader_set = ShaderSet {
             vertex: vs_module,
             fragment: fs_module,
@@ -52,14 +65,74 @@ impl TexturePipeline {
 
         TexturePipeline {
             shaders: shader_set,
+            premultiplied_fragment_shader: premultiplied_fs_module,
             texture_format,
             layout: None,
             pipeline: None,
+            blend_mode: BlendMode::default(),
             vertex_buffer: None,
             index_buffer: None,
             bind_groups: None,
         }
     }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Changes how this pipeline blends into the target, rebuilding the render pipeline
+    /// right away if it was already initialized.
+    pub fn set_blend_mode(&mut self, device: &wgpu::Device, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+        if let Some(layout) = &self.layout {
+            self.pipeline = Some(self.create_render_pipeline(device, layout));
+        }
+    }
+
+    fn create_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+    ) -> wgpu::RenderPipeline {
+        let fragment_module = if self.blend_mode.premultiplies_in_shader() {
+            &self.shaders.fragment
+        } else {
+            &self.premultiplied_fragment_shader
+        };
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Texture Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &self.shaders.vertex,
+                entry_point: "main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: fragment_module,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: self.texture_format,
+                    color_blend: self.blend_mode.color_blend(),
+                    alpha_blend: self.blend_mode.alpha_blend(),
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::Back,
+                polygon_mode: wgpu::PolygonMode::Fill,
+            },
+            depth_stencil: Some(DepthMode::Disabled.depth_stencil()),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        })
+    }
 }
 
 impl Pipeline for TexturePipeline {
@@ -94,38 +167,7 @@ impl Pipeline for TexturePipeline {
                 bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
                 push_constant_ranges: &[],
             });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Texture Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &self.shaders.vertex,
-                entry_point: "main",
-                buffers: &[Vertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &self.shaders.fragment,
-                entry_point: "main",
-                targets: &[wgpu::ColorTargetState {
-                    format: self.texture_format,
-                    color_blend: wgpu::BlendState::REPLACE,
-                    alpha_blend: wgpu::BlendState::REPLACE,
-                    write_mask: wgpu::ColorWrite::ALL,
-                }],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: wgpu::CullMode::Back,
-                polygon_mode: wgpu::PolygonMode::Fill,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-        });
+        let render_pipeline = self.create_render_pipeline(device, &render_pipeline_layout);
 
         self.layout = Some(render_pipeline_layout);
         self.pipeline = Some(render_pipeline);
@@ -135,6 +177,19 @@ impl Pipeline for TexturePipeline {
     }
 
     fn resize(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue, _width: u32, _height: u32) {}
+    fn prepare(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) {}
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(self.pipeline.as_ref().unwrap());
+        for (i, bind_group) in self.bind_groups.as_ref().unwrap().iter().enumerate() {
+            render_pass.set_bind_group(i as u32, bind_group, &[]);
+        }
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.as_ref().unwrap().slice(..));
+        render_pass.set_index_buffer(
+            self.index_buffer.as_ref().unwrap().slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+        render_pass.draw_indexed(0..self.index_number(), 0, 0..1);
+    }
     fn layout(&self) -> &Option<PipelineLayout> {
         &self.layout
     }
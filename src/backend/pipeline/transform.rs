@@ -0,0 +1,22 @@
+use crate::backend::instance::SpriteInstance;
+use crate::backend::vertex::Vertex;
+
+/// Where sprites are turned into transformed quads.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum TransformMode {
+    /// Generate four vertices per sprite on the CPU.
+    #[default]
+    Cpu,
+    /// Upload one instance per sprite and expand it into a quad in the vertex shader.
+    Instanced,
+}
+
+impl TransformMode {
+    /// Bytes a single sprite takes up in the vertex buffer.
+    pub fn sprite_size(&self) -> usize {
+        match self {
+            TransformMode::Cpu => 4 * std::mem::size_of::<Vertex>(),
+            TransformMode::Instanced => std::mem::size_of::<SpriteInstance>(),
+        }
+    }
+}
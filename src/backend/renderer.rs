@@ -1,4 +1,5 @@
 use crate::backend::pipeline::Pipeline;
+use crate::backend::resource::texture::Texture;
 
 use crate::backend::swapchain::SwapChain;
 
@@ -12,6 +13,7 @@ pub struct Renderer<P: Pipeline> {
     device: wgpu::Device,
     queue: wgpu::Queue,
     swap_chain: SwapChain,
+    depth_texture: Texture,
     pipeline: P,
 
     pub width: u32,
@@ -62,6 +64,7 @@ impl<P: Pipeline> Renderer<P> {
             present_mode: wgpu::PresentMode::Mailbox,
         };
         let swap_chain = SwapChain::new(&device, &surface, swap_descriptor);
+        let depth_texture = Texture::depth(&device, size.width, size.height, Some("Depth Texture"));
 
         // Pipeline
         let mut pipeline = pipeline_builder(&device, swap_chain.descriptor.format);
@@ -73,6 +76,7 @@ impl<P: Pipeline> Renderer<P> {
             device,
             queue,
             swap_chain,
+            depth_texture,
             pipeline,
 
             width: size.width,
@@ -87,6 +91,7 @@ impl<P: Pipeline> Renderer<P> {
         if width != 0 && height != 0 {
             self.swap_chain
                 .resize(&self.device, &self.surface, width, height);
+            self.depth_texture = Texture::depth(&self.device, width, height, Some("Depth Texture"));
             self.pipeline
                 .resize(&self.device, &self.queue, width, height);
         }
@@ -99,6 +104,8 @@ impl<P: Pipeline> Renderer<P> {
     pub fn update(&mut self) {}
 
     pub fn render(&mut self) -> Result<(), wgpu::SwapChainError> {
+        self.pipeline.prepare(&self.device, &self.queue);
+
         let frame = self.swap_chain.get_current_frame()?;
         let mut encoder = self
             .device
@@ -122,27 +129,17 @@ impl<P: Pipeline> Renderer<P> {
                         store: true,
                     },
                 }],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: self.depth_texture.get_view(),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
             });
 
-            let vertex_buffer = self.pipeline.vertex_buffer().as_ref().unwrap();
-            let index_buffer = self.pipeline.index_buffer().as_ref().unwrap();
-            let number_of_indecies = self.pipeline.index_number();
-
-            render_pass.set_pipeline(&self.pipeline.pipeline().as_ref().unwrap());
-            for (i, bind_group) in self.pipeline.groups().as_ref().unwrap().iter().enumerate() {
-                render_pass.set_bind_group(i as u32, bind_group, &[]);
-            }
-            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            /*
-            for i in 0..number_of_indecies / 6 {
-                let index = i * 6;
-                let vert_index = i * 4;
-                render_pass.draw_indexed(index..index + 6, vert_index as i32, 0..1);
-            }
-            */
-            render_pass.draw_indexed(0..number_of_indecies, 0, 0..1);
+            self.pipeline.draw(&mut render_pass);
         }
 
         // submit will accept anything that implements IntoIter
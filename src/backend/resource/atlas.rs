@@ -0,0 +1,348 @@
+use crate::backend::resource::texture::Texture;
+use crate::backend::sprite::SpriteDesc;
+use anyhow::*;
+use std::collections::HashMap;
+
+/// Where an image ended up in an atlas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+    /// Index of the page, also the texture index of sprites using the region when the
+    /// pages are given to the pipeline in order.
+    pub page: u32,
+    /// Top left corner in texels, ready for [`crate::backend::sprite::Sprites::add`].
+    pub source_position: ultraviolet::Vec2,
+    /// Size in texels, the size of the original image.
+    pub source_size: ultraviolet::Vec2,
+}
+
+impl AtlasRegion {
+    /// Sprite showing the region at its original size.
+    pub fn sprite_desc(&self) -> SpriteDesc {
+        SpriteDesc::from_source_rect(self.source_position, self.source_size)
+            .with_texture_index(self.page)
+    }
+}
+
+/// Collects images and packs them into as few atlas pages as possible, so sprites using
+/// any of them can share a texture and be drawn in one batch.
+///
+/// Images are sorted by height and placed on shelves, left to right and top to bottom,
+/// opening a new page when none of the open ones has room left.
+pub struct AtlasBuilder {
+    page_width: u32,
+    page_height: u32,
+    padding: u32,
+    extrusion: u32,
+    images: Vec<(String, image::RgbaImage)>,
+}
+
+impl AtlasBuilder {
+    /// Every page is `page_width` x `page_height` texels, keep that within the limits of
+    /// the GPU, e.g. 2048 x 2048.
+    pub fn new(page_width: u32, page_height: u32) -> Self {
+        Self {
+            page_width,
+            page_height,
+            padding: 0,
+            extrusion: 0,
+            images: vec![],
+        }
+    }
+
+    /// Empty texels left between images, so filtering never reaches across to a
+    /// neighbour.
+    pub fn with_padding(mut self, texels: u32) -> Self {
+        self.padding = texels;
+        self
+    }
+    /// Repeats the outermost texels of every image this many times around it, hides the
+    /// seams between sprites that are tiled or land on fractional positions.
+    pub fn with_extrusion(mut self, texels: u32) -> Self {
+        self.extrusion = texels;
+        self
+    }
+
+    /// Adds an image to be packed, its region is looked up by `name` afterwards.
+    pub fn add(&mut self, name: impl Into<String>, image: &image::DynamicImage) {
+        self.images.push((name.into(), image.to_rgba8()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Packs the images into pages in memory, without touching the GPU.
+    pub fn pack(&self) -> Result<PackedAtlas> {
+        let mut regions = HashMap::with_capacity(self.images.len());
+        let mut pages: Vec<(image::RgbaImage, Vec<Shelf>, u32)> = vec![];
+
+        // Tallest first, so the shelves waste little space above shorter images
+        let mut order = (0..self.images.len()).collect::<Vec<_>>();
+        order.sort_by_key(|index| {
+            let image = &self.images[*index].1;
+            std::cmp::Reverse((image.height(), image.width()))
+        });
+
+        for index in order {
+            let (name, image) = &self.images[index];
+            let cell_width = image.width() + 2 * self.extrusion;
+            let cell_height = image.height() + 2 * self.extrusion;
+            if cell_width > self.page_width || cell_height > self.page_height {
+                bail!(
+                    "Image {} ({}x{}) doesn't fit into a {}x{} atlas page",
+                    name,
+                    image.width(),
+                    image.height(),
+                    self.page_width,
+                    self.page_height
+                );
+            }
+
+            let mut placement = None;
+            for (page_index, (_, shelves, next_y)) in pages.iter_mut().enumerate() {
+                if let Some(position) = place(
+                    shelves,
+                    next_y,
+                    (cell_width, cell_height),
+                    (self.page_width, self.page_height),
+                    self.padding,
+                ) {
+                    placement = Some((page_index, position));
+                    break;
+                }
+            }
+            let (page_index, (x, y)) = match placement {
+                Some(placement) => placement,
+                None => {
+                    let mut shelves = vec![];
+                    let mut next_y = 0;
+                    let position = place(
+                        &mut shelves,
+                        &mut next_y,
+                        (cell_width, cell_height),
+                        (self.page_width, self.page_height),
+                        self.padding,
+                    )
+                    .unwrap();
+                    pages.push((
+                        image::RgbaImage::new(self.page_width, self.page_height),
+                        shelves,
+                        next_y,
+                    ));
+                    (pages.len() - 1, position)
+                }
+            };
+
+            blit_extruded(&mut pages[page_index].0, image, x, y, self.extrusion);
+            let previous = regions.insert(
+                name.clone(),
+                AtlasRegion {
+                    page: page_index as u32,
+                    source_position: ultraviolet::Vec2::new(
+                        (x + self.extrusion) as f32,
+                        (y + self.extrusion) as f32,
+                    ),
+                    source_size: ultraviolet::Vec2::new(
+                        image.width() as f32,
+                        image.height() as f32,
+                    ),
+                },
+            );
+            if previous.is_some() {
+                bail!("Image {} was added to the atlas twice", name);
+            }
+        }
+
+        Ok(PackedAtlas {
+            pages: pages.into_iter().map(|(page, _, _)| page).collect(),
+            regions,
+        })
+    }
+
+    /// Packs the images and uploads every page as a texture.
+    pub fn build(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: Option<&str>,
+    ) -> Result<Atlas> {
+        self.pack()?.upload(device, queue, label)
+    }
+}
+
+/// Pages and regions of an atlas that hasn't been uploaded yet, see [`AtlasBuilder::pack`].
+pub struct PackedAtlas {
+    pub pages: Vec<image::RgbaImage>,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl PackedAtlas {
+    pub fn upload(
+        self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: Option<&str>,
+    ) -> Result<Atlas> {
+        let page_sizes = self.pages.iter().map(|page| page.dimensions()).collect();
+        let pages = self
+            .pages
+            .into_iter()
+            .map(|page| {
+                Texture::from_image(device, queue, &image::DynamicImage::ImageRgba8(page), label)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Atlas {
+            pages,
+            page_sizes,
+            regions: self.regions,
+        })
+    }
+}
+
+/// Textures holding many images each, see [`AtlasBuilder`].
+pub struct Atlas {
+    /// In page order, bind them in this order so page indices are texture indices.
+    pub pages: Vec<Texture>,
+    page_sizes: Vec<(u32, u32)>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl Atlas {
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.regions.get(name).copied()
+    }
+    pub fn regions(&self) -> &HashMap<String, AtlasRegion> {
+        &self.regions
+    }
+    /// Sizes of the pages in order, the texture sizes for
+    /// [`crate::backend::pipeline::sprite::SpritePipeline::with_texture_sizes`].
+    pub fn page_sizes(&self) -> &[(u32, u32)] {
+        &self.page_sizes
+    }
+}
+
+/// Row of images of at most `height` texels on an atlas page.
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Left edge of the space still free on the shelf.
+    next_x: u32,
+}
+
+/// Finds room for a cell on the first shelf it fits on, opening a new shelf below the
+/// others if there is none. `None` if the page is full.
+fn place(
+    shelves: &mut Vec<Shelf>,
+    next_y: &mut u32,
+    (cell_width, cell_height): (u32, u32),
+    (page_width, page_height): (u32, u32),
+    padding: u32,
+) -> Option<(u32, u32)> {
+    for shelf in shelves.iter_mut() {
+        if cell_height <= shelf.height && shelf.next_x + cell_width <= page_width {
+            let position = (shelf.next_x, shelf.y);
+            shelf.next_x += cell_width + padding;
+            return Some(position);
+        }
+    }
+
+    if *next_y + cell_height > page_height {
+        return None;
+    }
+    shelves.push(Shelf {
+        y: *next_y,
+        height: cell_height,
+        next_x: cell_width + padding,
+    });
+    let position = (0, *next_y);
+    *next_y += cell_height + padding;
+
+    Some(position)
+}
+
+/// Copies `image` into `page` with its top left corner at `x + extrusion, y + extrusion`,
+/// repeating its edges `extrusion` texels outwards.
+fn blit_extruded(
+    page: &mut image::RgbaImage,
+    image: &image::RgbaImage,
+    x: u32,
+    y: u32,
+    extrusion: u32,
+) {
+    if image.width() == 0 || image.height() == 0 {
+        return;
+    }
+
+    for cell_y in 0..image.height() + 2 * extrusion {
+        let source_y = cell_y.saturating_sub(extrusion).min(image.height() - 1);
+        for cell_x in 0..image.width() + 2 * extrusion {
+            let source_x = cell_x.saturating_sub(extrusion).min(image.width() - 1);
+            page.put_pixel(x + cell_x, y + cell_y, *image.get_pixel(source_x, source_y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, value: u8) -> image::DynamicImage {
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            width,
+            height,
+            image::Rgba([value, value, value, 255]),
+        ))
+    }
+
+    #[test]
+    fn images_are_packed_without_overlapping() {
+        let mut builder = AtlasBuilder::new(64, 64).with_padding(1).with_extrusion(1);
+        builder.add("tall", &solid(10, 40, 1));
+        builder.add("wide", &solid(50, 10, 2));
+        builder.add("small", &solid(8, 8, 3));
+        builder.add("big", &solid(60, 60, 4));
+        let atlas = builder.pack().unwrap();
+
+        // The big image takes up a page of its own
+        assert_eq!(atlas.pages.len(), 2);
+        assert_eq!(atlas.regions["big"].page, 0);
+
+        let regions = ["tall", "wide", "small"]
+            .iter()
+            .map(|name| atlas.regions[*name])
+            .collect::<Vec<_>>();
+        for (i, a) in regions.iter().enumerate() {
+            assert_eq!(a.page, 1);
+            for b in &regions[i + 1..] {
+                let apart = a.source_position.x + a.source_size.x + 2.0 <= b.source_position.x
+                    || b.source_position.x + b.source_size.x + 2.0 <= a.source_position.x
+                    || a.source_position.y + a.source_size.y + 2.0 <= b.source_position.y
+                    || b.source_position.y + b.source_size.y + 2.0 <= a.source_position.y;
+                assert!(apart, "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        // Edges are extruded by a texel
+        let small = atlas.regions["small"];
+        let (x, y) = (
+            small.source_position.x as u32,
+            small.source_position.y as u32,
+        );
+        assert_eq!(atlas.pages[1].get_pixel(x - 1, y - 1)[0], 3);
+        assert_eq!(atlas.pages[1].get_pixel(x + 8, y + 7)[0], 3);
+    }
+
+    #[test]
+    fn images_larger_than_a_page_are_rejected() {
+        let mut builder = AtlasBuilder::new(32, 32).with_extrusion(1);
+        builder.add("fits", &solid(30, 30, 1));
+        builder.pack().unwrap();
+
+        builder.add("too_big", &solid(31, 10, 1));
+        assert!(builder.pack().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod atlas;
 pub mod sampler;
 pub mod texture;
 pub mod uniform;
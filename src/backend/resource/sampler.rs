@@ -14,6 +14,8 @@ impl Sampler {
         }
     }
 
+    /// Nearest-neighbour sampler for pixel art. The default `Sprites::uv_inset` keeps
+    /// sprites sampling sheets or atlases with it from bleeding between regions.
     pub fn pixel(device: &wgpu::Device) -> Self {
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -18,6 +18,8 @@ pub struct Texture {
 }
 
 impl Texture {
+    pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
     pub fn from_swap_chain_texture(texture: wgpu::SwapChainTexture) -> Self {
         Self {
             source: TextureSource::SwapChainTexture { texture },
@@ -80,6 +82,28 @@ impl Texture {
         })
     }
 
+    /// Depth attachment of the given size, recreate it whenever the swap chain is resized.
+    pub fn depth(device: &wgpu::Device, width: u32, height: u32, label: Option<&str>) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::DEPTH_FORMAT,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            source: TextureSource::Texture { texture, view },
+        }
+    }
+
     pub fn get_view(&self) -> &wgpu::TextureView {
         let view = match &self.source {
             TextureSource::Texture { texture: _, ref view } => view,
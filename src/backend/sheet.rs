@@ -0,0 +1,709 @@
+use crate::backend::animation::{Clip, Frame, PlaybackMode};
+use crate::backend::sprite::{SpriteDesc, SpriteHandle, Sprites};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// One frame of a [`SpriteSheet`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SheetFrame {
+    pub name: String,
+    /// Top left corner of the frame in the texture in texels.
+    pub source_position: ultraviolet::Vec2,
+    /// Size of the frame in the texture in texels. Width and height of rotated frames are
+    /// swapped compared to how they're shown.
+    pub source_size: ultraviolet::Vec2,
+    /// Stored turned 90 degrees clockwise to pack tighter.
+    pub rotated: bool,
+    /// Where the trimmed frame sits within the original, from its top left corner.
+    pub trim_offset: ultraviolet::Vec2,
+    /// Size of the original frame, before transparent borders were trimmed.
+    pub frame_size: ultraviolet::Vec2,
+    /// Point the frame is placed by, relative to the original frame from its top left
+    /// corner. Its bottom left corner unless the sheet says otherwise.
+    pub pivot: ultraviolet::Vec2,
+    /// In seconds, `None` if the sheet doesn't say.
+    pub duration: Option<f32>,
+}
+
+impl SheetFrame {
+    /// Sprite origin that puts the pivot at the sprite's position, accounting for trimming
+    /// and rotation.
+    pub fn origin(&self) -> ultraviolet::Vec2 {
+        let pivot = self.pivot * self.frame_size - self.trim_offset;
+        let shown_size = if self.rotated {
+            ultraviolet::Vec2::new(self.source_size.y, self.source_size.x)
+        } else {
+            self.source_size
+        };
+        // Sheets measure from the top left, sprite origins from the bottom left
+        let origin = ultraviolet::Vec2::new(pivot.x / shown_size.x, 1.0 - pivot.y / shown_size.y);
+
+        if self.rotated {
+            // The stored quad is turned back by 90 degrees, its x axis is shown upwards
+            ultraviolet::Vec2::new(origin.y, 1.0 - origin.x)
+        } else {
+            origin
+        }
+    }
+
+    /// Angle in degrees that turns rotated frames upright again, add the sprite's own
+    /// angle to it.
+    pub fn angle(&self) -> f32 {
+        if self.rotated {
+            90.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Sprite showing the frame upright with its pivot at the position.
+    pub fn sprite_desc(&self) -> SpriteDesc {
+        SpriteDesc::from_source_rect(self.source_position, self.source_size)
+            .with_origin(self.origin())
+            .with_angle(self.angle())
+    }
+}
+
+/// A named run of frames of a [`SpriteSheet`], from Aseprite's tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SheetTag {
+    pub name: String,
+    /// Frame indices in the order they're played.
+    pub frames: Vec<usize>,
+    pub mode: PlaybackMode,
+}
+
+/// Frames and tags of a texture exported by a sprite sheet packer, see [`SpriteGrid`]
+/// for sheets that are plain grids instead.
+///
+/// Reads the JSON written by TexturePacker and Aseprite, in both their hash and array
+/// flavours. Aseprite's format is TexturePacker's plus frame durations and tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteSheet {
+    /// File name of the texture, relative to the JSON.
+    pub image: Option<String>,
+    /// Size of the texture in texels, if the sheet says.
+    pub size: Option<(u32, u32)>,
+    frames: Vec<SheetFrame>,
+    frame_indices: HashMap<String, usize>,
+    tags: Vec<SheetTag>,
+}
+
+impl SpriteSheet {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let raw: RawSheet = serde_json::from_str(json).context("Invalid sprite sheet JSON")?;
+        let raw_frames = match raw.frames {
+            RawFrames::Array(frames) => frames
+                .into_iter()
+                .map(|frame| (frame.filename.clone().unwrap_or_default(), frame))
+                .collect(),
+            RawFrames::Hash(OrderedFrames(frames)) => frames,
+        };
+
+        let frames = raw_frames
+            .into_iter()
+            .map(|(name, frame)| frame.into_sheet_frame(name))
+            .collect::<Vec<_>>();
+        let frame_indices = frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| (frame.name.clone(), index))
+            .collect();
+
+        let tags = raw
+            .meta
+            .frame_tags
+            .into_iter()
+            .map(|tag| tag.into_sheet_tag(frames.len()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            image: raw.meta.image,
+            size: raw.meta.size.map(|size| (size.w as u32, size.h as u32)),
+            frames,
+            frame_indices,
+            tags,
+        })
+    }
+
+    /// Frames in the order of the sheet, which is the frame order of Aseprite exports.
+    pub fn frames(&self) -> &[SheetFrame] {
+        &self.frames
+    }
+    pub fn frame(&self, name: &str) -> Option<&SheetFrame> {
+        Some(&self.frames[*self.frame_indices.get(name)?])
+    }
+    pub fn frame_index(&self, name: &str) -> Option<usize> {
+        self.frame_indices.get(name).copied()
+    }
+
+    pub fn tags(&self) -> &[SheetTag] {
+        &self.tags
+    }
+    pub fn tag(&self, name: &str) -> Option<&SheetTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Clip playing the frames at `frames` in that order. Frames the sheet has no
+    /// duration for are shown for `default_duration` seconds.
+    ///
+    /// Fails without frames, for indices past the last frame and for frames that end up
+    /// without a positive duration.
+    ///
+    /// If any of the frames is rotated, every frame also sets the sprite's angle to
+    /// [`SheetFrame::angle`] so they're all shown upright, replacing the sprite's own angle.
+    pub fn clip(
+        &self,
+        frames: &[usize],
+        mode: PlaybackMode,
+        default_duration: f32,
+    ) -> Result<Clip> {
+        if frames.is_empty() {
+            bail!("Clip without frames");
+        }
+
+        let any_rotated = frames
+            .iter()
+            .any(|index| self.frames.get(*index).is_some_and(|frame| frame.rotated));
+        let frames = frames
+            .iter()
+            .map(|index| {
+                let frame = self.frames.get(*index).with_context(|| {
+                    format!(
+                        "Frame {} is out of range of {} frames",
+                        index,
+                        self.frames.len()
+                    )
+                })?;
+                let duration = frame.duration.unwrap_or(default_duration);
+                if duration.is_nan() || duration <= 0.0 {
+                    bail!(
+                        "Frame {} would be shown for {} seconds",
+                        frame.name,
+                        duration
+                    );
+                }
+
+                let clip_frame = Frame::new(frame.source_position, frame.source_size, duration)
+                    .with_origin(frame.origin());
+                Ok(if any_rotated {
+                    clip_frame.with_angle(frame.angle())
+                } else {
+                    clip_frame
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Clip::new(frames, mode))
+    }
+
+    /// Clip of the tag called `name`, see [`SpriteSheet::clip`].
+    pub fn tag_clip(&self, name: &str, default_duration: f32) -> Result<Clip> {
+        let tag = self
+            .tag(name)
+            .with_context(|| format!("Sprite sheet has no tag {}", name))?;
+        self.clip(&tag.frames, tag.mode, default_duration)
+    }
+}
+
+/// One cell of a [`SpriteGrid`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GridCell {
+    /// Row by row from the top left.
+    pub index: u32,
+    pub column: u32,
+    pub row: u32,
+    /// Top left corner in texels.
+    pub source_position: ultraviolet::Vec2,
+    /// Size in texels, the cell size of the grid.
+    pub source_size: ultraviolet::Vec2,
+}
+
+impl GridCell {
+    /// Switches `sprite` to the cell, `None` if it was removed.
+    pub fn apply(&self, sprites: &mut Sprites, sprite: SpriteHandle) -> Option<()> {
+        sprites.set_source_position(sprite, self.source_position)?;
+        sprites.set_source_size(sprite, self.source_size)
+    }
+
+    pub fn sprite_desc(&self) -> SpriteDesc {
+        SpriteDesc::from_source_rect(self.source_position, self.source_size)
+    }
+}
+
+/// A texture cut into equally sized cells, like most hand made sprite sheets.
+///
+/// Cells start `margin` texels in from the top left corner of the texture and are
+/// `spacing` texels apart. Cells that don't fit completely are left out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteGrid {
+    texture_size: (u32, u32),
+    cell_size: (u32, u32),
+    margin: u32,
+    spacing: u32,
+}
+
+impl SpriteGrid {
+    /// Cells of `cell_size` texels packed tightly into a texture of `texture_size` texels.
+    pub fn new(texture_size: (u32, u32), cell_size: (u32, u32)) -> Self {
+        assert!(cell_size.0 > 0 && cell_size.1 > 0, "Grid cells need a size");
+
+        Self {
+            texture_size,
+            cell_size,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    pub fn with_margin(mut self, texels: u32) -> Self {
+        self.margin = texels;
+        self
+    }
+    pub fn with_spacing(mut self, texels: u32) -> Self {
+        self.spacing = texels;
+        self
+    }
+
+    pub fn columns(&self) -> u32 {
+        fitting_cells(
+            self.texture_size.0,
+            self.cell_size.0,
+            self.margin,
+            self.spacing,
+        )
+    }
+    pub fn rows(&self) -> u32 {
+        fitting_cells(
+            self.texture_size.1,
+            self.cell_size.1,
+            self.margin,
+            self.spacing,
+        )
+    }
+    pub fn len(&self) -> usize {
+        (self.columns() * self.rows()) as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn cell(&self, column: u32, row: u32) -> Option<GridCell> {
+        if column >= self.columns() || row >= self.rows() {
+            return None;
+        }
+
+        Some(GridCell {
+            index: row * self.columns() + column,
+            column,
+            row,
+            source_position: ultraviolet::Vec2::new(
+                (self.margin + column * (self.cell_size.0 + self.spacing)) as f32,
+                (self.margin + row * (self.cell_size.1 + self.spacing)) as f32,
+            ),
+            source_size: ultraviolet::Vec2::new(self.cell_size.0 as f32, self.cell_size.1 as f32),
+        })
+    }
+    /// Cell at `index`, counting row by row from the top left.
+    pub fn cell_at(&self, index: u32) -> Option<GridCell> {
+        let columns = self.columns();
+        if columns == 0 {
+            return None;
+        }
+        self.cell(index % columns, index / columns)
+    }
+
+    /// Every cell, row by row from the top left.
+    pub fn cells(&self) -> impl Iterator<Item = GridCell> + '_ {
+        (0..self.len() as u32).filter_map(move |index| self.cell_at(index))
+    }
+    /// Cells of one row from left to right, a common layout for animations.
+    pub fn row(&self, row: u32) -> impl Iterator<Item = GridCell> + '_ {
+        let columns = if row < self.rows() { self.columns() } else { 0 };
+        (0..columns).filter_map(move |column| self.cell(column, row))
+    }
+
+    /// Clip playing a row of cells from left to right, each for `frame_duration` seconds.
+    /// `None` if the row doesn't exist or `frame_duration` isn't positive.
+    pub fn row_clip(&self, row: u32, frame_duration: f32, mode: PlaybackMode) -> Option<Clip> {
+        if row >= self.rows() || self.columns() == 0 {
+            return None;
+        }
+        if frame_duration.is_nan() || frame_duration <= 0.0 {
+            return None;
+        }
+
+        Some(Clip::uniform(
+            self.row(row)
+                .map(|cell| (cell.source_position, cell.source_size)),
+            frame_duration,
+            mode,
+        ))
+    }
+}
+
+/// Cells of `cell` texels that fit into `length` after the margins, `spacing` apart.
+fn fitting_cells(length: u32, cell: u32, margin: u32, spacing: u32) -> u32 {
+    (length.saturating_sub(2 * margin) + spacing) / (cell + spacing)
+}
+
+#[derive(Deserialize)]
+struct RawRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct RawSize {
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct RawPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFrame {
+    /// Only in the array flavour, the hash flavour uses it as the key.
+    filename: Option<String>,
+    frame: RawRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<RawRect>,
+    source_size: Option<RawSize>,
+    pivot: Option<RawPoint>,
+    /// In milliseconds.
+    duration: Option<f32>,
+}
+
+impl RawFrame {
+    fn into_sheet_frame(self, name: String) -> SheetFrame {
+        // Rotated frames list the size they're shown at, not the size in the texture
+        let source_size = if self.rotated {
+            ultraviolet::Vec2::new(self.frame.h, self.frame.w)
+        } else {
+            ultraviolet::Vec2::new(self.frame.w, self.frame.h)
+        };
+        let trim_offset = self
+            .sprite_source_size
+            .map(|rect| ultraviolet::Vec2::new(rect.x, rect.y))
+            .unwrap_or_else(ultraviolet::Vec2::zero);
+        let frame = &self.frame;
+        let frame_size = self
+            .source_size
+            .map(|size| ultraviolet::Vec2::new(size.w, size.h))
+            .unwrap_or_else(|| ultraviolet::Vec2::new(frame.w, frame.h));
+
+        SheetFrame {
+            name,
+            source_position: ultraviolet::Vec2::new(self.frame.x, self.frame.y),
+            source_size,
+            rotated: self.rotated,
+            trim_offset,
+            frame_size,
+            pivot: self
+                .pivot
+                .map(|pivot| ultraviolet::Vec2::new(pivot.x, pivot.y))
+                .unwrap_or_else(|| ultraviolet::Vec2::new(0.0, 1.0)),
+            duration: self.duration.map(|duration| duration / 1000.0),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFrames {
+    Array(Vec<RawFrame>),
+    Hash(OrderedFrames),
+}
+
+/// Frames of the hash flavour in the order of the file, which is the frame order.
+struct OrderedFrames(Vec<(String, RawFrame)>);
+
+impl<'de> Deserialize<'de> for OrderedFrames {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = OrderedFrames;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map of frame names to frames")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut frames = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(entry) = map.next_entry()? {
+                    frames.push(entry);
+                }
+                Ok(OrderedFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMeta {
+    image: Option<String>,
+    size: Option<RawSize>,
+    #[serde(default)]
+    frame_tags: Vec<RawTag>,
+}
+
+#[derive(Deserialize)]
+struct RawTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: Option<String>,
+    /// Times the tag is played, a string in Aseprite's exports.
+    #[serde(default)]
+    repeat: Option<serde_json::Value>,
+}
+
+impl RawTag {
+    fn into_sheet_tag(self, frame_count: usize) -> Result<SheetTag> {
+        if self.from > self.to || self.to >= frame_count {
+            bail!(
+                "Tag {} spans frames {} to {} of {}",
+                self.name,
+                self.from,
+                self.to,
+                frame_count
+            );
+        }
+
+        let mut frames = (self.from..=self.to).collect::<Vec<_>>();
+        let mode = match self.direction.as_deref().unwrap_or("forward") {
+            "forward" => PlaybackMode::Loop,
+            "reverse" => {
+                frames.reverse();
+                PlaybackMode::Loop
+            }
+            "pingpong" => PlaybackMode::PingPong,
+            "pingpong_reverse" => {
+                frames.reverse();
+                PlaybackMode::PingPong
+            }
+            direction => bail!("Tag {} has unknown direction {}", self.name, direction),
+        };
+        // Tags repeated once stop at the end, counts above that can only loop forever
+        let repeat = self.repeat.as_ref().and_then(|repeat| match repeat {
+            serde_json::Value::Number(number) => number.as_u64(),
+            serde_json::Value::String(string) => string.parse().ok(),
+            _ => None,
+        });
+        let mode = match (mode, repeat) {
+            (PlaybackMode::Loop, Some(1)) => PlaybackMode::Once,
+            (mode, _) => mode,
+        };
+
+        Ok(SheetTag {
+            name: self.name,
+            frames,
+            mode,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSheet {
+    frames: RawFrames,
+    meta: RawMeta,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::animation::Animations;
+    use crate::backend::vertex::bounds;
+
+    /// Corners of the quad of a sprite added from `desc` at `position`.
+    fn desc_bounds(desc: SpriteDesc, position: ultraviolet::Vec2) -> ([f32; 2], [f32; 2]) {
+        let mut sprites = Sprites::new();
+        sprites.add_desc(desc.with_position(position));
+        bounds(&sprites.vertices(0..1, &[(64, 64)]))
+    }
+
+    fn assert_near(actual: ([f32; 2], [f32; 2]), expected: ([f32; 2], [f32; 2])) {
+        let pairs = actual
+            .0
+            .iter()
+            .chain(&actual.1)
+            .zip(expected.0.iter().chain(&expected.1));
+        for (actual_value, expected_value) in pairs {
+            assert!(
+                (actual_value - expected_value).abs() < 1e-3,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn texture_packer_frames_are_placed_by_their_pivot() {
+        let sheet = SpriteSheet::from_json(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/texture_packer.json"
+        )))
+        .unwrap();
+        assert_eq!(sheet.image.as_deref(), Some("items.png"));
+        assert_eq!(sheet.size, Some((64, 64)));
+        assert_eq!(sheet.frame_index("sword.png"), Some(1));
+
+        // Trimmed: pivot at the bottom center of the 32x32 original, the trimmed 20x30
+        // sits 6 texels in and 1 texel above its bottom
+        let gem = sheet.frame("gem.png").unwrap();
+        assert_eq!(gem.source_size, ultraviolet::Vec2::new(20.0, 30.0));
+        assert_near(
+            desc_bounds(gem.sprite_desc(), ultraviolet::Vec2::new(100.0, 100.0)),
+            ([90.0, 101.0], [110.0, 131.0]),
+        );
+
+        // Rotated: stored as 12x40, shown as 40x12 pivoting on the middle of its left edge
+        let sword = sheet.frame("sword.png").unwrap();
+        assert_eq!(sword.source_size, ultraviolet::Vec2::new(12.0, 40.0));
+        assert_near(
+            desc_bounds(sword.sprite_desc(), ultraviolet::Vec2::zero()),
+            ([0.0, -6.0], [40.0, 6.0]),
+        );
+
+        // TexturePacker has no durations, the default has to be usable
+        assert!(sheet.clip(&[0, 1], PlaybackMode::Once, 0.0).is_err());
+    }
+
+    #[test]
+    fn clips_turn_rotated_frames_upright() {
+        let sheet = SpriteSheet::from_json(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/texture_packer.json"
+        )))
+        .unwrap();
+        let clip = sheet.clip(&[0, 1], PlaybackMode::Once, 0.1).unwrap();
+        let angles = clip
+            .frames()
+            .iter()
+            .map(|frame| frame.angle)
+            .collect::<Vec<_>>();
+        assert_eq!(angles, vec![Some(0.0), Some(90.0)]);
+
+        let mut sprites = Sprites::new();
+        let sprite = sprites.add_desc(sheet.frame("gem.png").unwrap().sprite_desc());
+        let mut animations = Animations::new();
+        let clip = animations.add_clip(clip);
+        animations.play(&mut sprites, sprite, clip).unwrap();
+        assert_near(
+            bounds(&sprites.vertices(0..1, &[(64, 64)])),
+            ([-10.0, 1.0], [10.0, 31.0]),
+        );
+
+        // The sword is turned back upright, the same as its own sprite would be
+        animations.advance(&mut sprites, 0.15);
+        assert_eq!(animations.frame(sprite), Some(1));
+        assert_near(
+            bounds(&sprites.vertices(0..1, &[(64, 64)])),
+            ([0.0, -6.0], [40.0, 6.0]),
+        );
+    }
+
+    #[test]
+    fn aseprite_tags_become_clips() {
+        let sheet = SpriteSheet::from_json(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/aseprite.json"
+        )))
+        .unwrap();
+
+        // The hash keeps the order of the file rather than sorting by name
+        let names = sheet
+            .frames()
+            .iter()
+            .map(|frame| frame.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "knight idle 0",
+                "knight idle 1",
+                "knight attack 0",
+                "knight attack 1",
+                "knight attack 2"
+            ]
+        );
+
+        let idle = sheet.tag("idle").unwrap();
+        assert_eq!(
+            (idle.frames.clone(), idle.mode),
+            (vec![0, 1], PlaybackMode::Loop)
+        );
+        let attack = sheet.tag("attack").unwrap();
+        assert_eq!(attack.mode, PlaybackMode::PingPong);
+        let retreat = sheet.tag("retreat").unwrap();
+        assert_eq!(
+            (retreat.frames.clone(), retreat.mode),
+            (vec![4, 3, 2], PlaybackMode::Once)
+        );
+
+        let clip = sheet.tag_clip("attack", 0.1).unwrap();
+        let durations = clip
+            .frames()
+            .iter()
+            .map(|frame| frame.duration)
+            .collect::<Vec<_>>();
+        assert_eq!(durations, vec![0.05, 0.15, 0.2]);
+        assert_eq!(
+            clip.frames()[1].source_position,
+            ultraviolet::Vec2::new(48.0, 0.0)
+        );
+
+        // Anything that would make a broken clip is an error instead
+        assert!(sheet.tag_clip("jump", 0.1).is_err());
+        assert!(sheet.clip(&[], PlaybackMode::Loop, 0.1).is_err());
+        assert!(sheet.clip(&[0, 5], PlaybackMode::Loop, 0.1).is_err());
+    }
+
+    #[test]
+    fn grids_are_sliced_around_margins_and_spacing() {
+        let grid = SpriteGrid::new((100, 50), (16, 16))
+            .with_margin(2)
+            .with_spacing(1);
+        assert_eq!((grid.columns(), grid.rows()), (5, 2));
+
+        let cell = grid.cell(4, 1).unwrap();
+        assert_eq!(cell.index, 9);
+        assert_eq!(cell.source_position, ultraviolet::Vec2::new(70.0, 19.0));
+        assert_eq!(grid.cell_at(9), Some(cell));
+        assert_eq!(grid.cell(5, 0), None);
+        assert_eq!(grid.cells().count(), 10);
+
+        let mut sprites = Sprites::new();
+        let sprite = sprites.add_desc(grid.cell_at(0).unwrap().sprite_desc());
+        cell.apply(&mut sprites, sprite).unwrap();
+        assert_eq!(sprites.source_position(sprite), Some(cell.source_position));
+        assert_eq!(
+            sprites.source_size(sprite),
+            Some(ultraviolet::Vec2::new(16.0, 16.0))
+        );
+
+        let clip = grid.row_clip(1, 0.1, PlaybackMode::Loop).unwrap();
+        assert_eq!(clip.frames().len(), 5);
+        assert_eq!(clip.frames()[4].source_position, cell.source_position);
+        assert!(grid.row_clip(1, 0.0, PlaybackMode::Loop).is_none());
+        assert!(grid.row_clip(1, f32::NAN, PlaybackMode::Loop).is_none());
+    }
+}
@@ -1,16 +1,32 @@
 use anyhow::Result;
-use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use bytemuck::Zeroable;
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator,
+};
+use rayon::slice::ParallelSliceMut;
 use std::{
+    ops::Range,
     sync::mpsc::{channel, Receiver, Sender},
     thread::JoinHandle,
 };
 
+mod columns;
+mod nine_slice;
+mod view;
+
+pub use columns::ColumnsMut;
+pub use nine_slice::{Borders, NineSlice};
+pub use view::{SpriteMut, SpriteRef};
+
+use crate::backend::instance::SpriteInstance;
+use crate::backend::parallelism::Parallelism;
 use crate::backend::vertex::Vertex;
 
-const BASE_VEC_A: ultraviolet::Vec3 = ultraviolet::Vec3::new(0.0, 1.0, 1.0);
-const BASE_VEC_B: ultraviolet::Vec3 = ultraviolet::Vec3::new(0.0, 0.0, 1.0);
-const BASE_VEC_C: ultraviolet::Vec3 = ultraviolet::Vec3::new(1.0, 0.0, 1.0);
-const BASE_VEC_D: ultraviolet::Vec3 = ultraviolet::Vec3::new(1.0, 1.0, 1.0);
+const BASE_VEC_A: ultraviolet::Vec2 = ultraviolet::Vec2::new(0.0, 1.0);
+const BASE_VEC_B: ultraviolet::Vec2 = ultraviolet::Vec2::new(0.0, 0.0);
+const BASE_VEC_C: ultraviolet::Vec2 = ultraviolet::Vec2::new(1.0, 0.0);
+const BASE_VEC_D: ultraviolet::Vec2 = ultraviolet::Vec2::new(1.0, 1.0);
 
 const BASE_UV_A: ultraviolet::Vec2 = ultraviolet::Vec2::new(0.0, 0.0);
 const BASE_UV_B: ultraviolet::Vec2 = ultraviolet::Vec2::new(0.0, 1.0);
@@ -19,39 +35,366 @@ const BASE_UV_D: ultraviolet::Vec2 = ultraviolet::Vec2::new(1.0, 0.0);
 
 const BASELINE_INDICES: &[u16] = &[0, 1, 3, 3, 1, 2];
 
-#[inline]
-fn calculate_scale_mat(
-    scale: ultraviolet::Vec2,
-    source_size: ultraviolet::Vec2,
-) -> ultraviolet::Mat3 {
-    ultraviolet::Mat3::from_nonuniform_scale(ultraviolet::Vec3::new(
-        source_size.x * scale.x,
-        source_size.y * scale.y,
-        1.0,
-    ))
+/// Most sprites whose four vertices each can still be addressed by 16-bit indices.
+pub const MAX_U16_SPRITES: usize = (u16::MAX as usize + 1) / 4;
+
+/// UV inset new sprites start with, in texels. Nearest sampling (`Sampler::pixel`) stays
+/// inside the source rectangle even on the edges of a quad where the UVs reach its far
+/// border, while no texel moves far enough to show.
+pub const DEFAULT_UV_INSET: f32 = 0.01;
+
+/// Sprites transformed together by [`Sprites::write_vertices_wide`].
+const LANES: usize = 8;
+
+/// Past this many separate dirty ranges everything is considered dirty, one big
+/// upload is cheaper than thousands of tiny ones.
+const MAX_DIRTY_RANGES: usize = 1024;
+
+/// 2D affine transform, the upper two rows of a homogeneous `Mat3`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Affine2 {
+    pub x_axis: ultraviolet::Vec2,
+    pub y_axis: ultraviolet::Vec2,
+    pub translation: ultraviolet::Vec2,
+}
+
+impl Affine2 {
+    pub const IDENTITY: Self = Self {
+        x_axis: ultraviolet::Vec2::new(1.0, 0.0),
+        y_axis: ultraviolet::Vec2::new(0.0, 1.0),
+        translation: ultraviolet::Vec2::new(0.0, 0.0),
+    };
+
+    /// Maps the unit quad onto a sprite: scaled to `source_size * scale` around `origin`
+    /// (relative to that size), skewed by `skew` degrees, rotated by `angle` degrees and
+    /// moved to `position`.
+    #[inline]
+    pub fn from_sprite(
+        position: ultraviolet::Vec2,
+        angle: f32,
+        scale: ultraviolet::Vec2,
+        skew: ultraviolet::Vec2,
+        source_size: ultraviolet::Vec2,
+        origin: ultraviolet::Vec2,
+    ) -> Self {
+        let size = source_size * scale;
+        let (sin, cos) = (angle * std::f32::consts::PI / 180.0).sin_cos();
+        // Skewing along x leans the y axis and the other way around
+        let skew_x = (skew.x * std::f32::consts::PI / 180.0).tan();
+        let skew_y = (skew.y * std::f32::consts::PI / 180.0).tan();
+        let x_axis = ultraviolet::Vec2::new(cos - sin * skew_y, sin + cos * skew_y) * size.x;
+        let y_axis = ultraviolet::Vec2::new(cos * skew_x - sin, sin * skew_x + cos) * size.y;
+
+        Self {
+            x_axis,
+            y_axis,
+            translation: position - x_axis * origin.x - y_axis * origin.y,
+        }
+    }
+
+    #[inline]
+    pub fn transform_point(&self, point: ultraviolet::Vec2) -> ultraviolet::Vec2 {
+        self.x_axis * point.x + self.y_axis * point.y + self.translation
+    }
+
+    /// Whether the transform mirrors, e.g. through a negative scale, which turns the
+    /// winding of the quad around.
+    #[inline]
+    pub fn is_mirroring(&self) -> bool {
+        self.x_axis.x * self.y_axis.y - self.x_axis.y * self.y_axis.x < 0.0
+    }
 }
+
+impl From<ultraviolet::Mat3> for Affine2 {
+    /// Drops the bottom row, which is `(0, 0, 1)` for any affine transform.
+    fn from(matrix: ultraviolet::Mat3) -> Self {
+        Self {
+            x_axis: matrix.cols[0].xy(),
+            y_axis: matrix.cols[1].xy(),
+            translation: matrix.cols[2].xy(),
+        }
+    }
+}
+
+/// Transform of a sprite from its columns. A custom transform maps the sprite's local
+/// space, `(0, 0)` to its source size with y up, and replaces everything but that size.
 #[inline]
-fn calculate_origin_translation_mat(
-    origin: ultraviolet::Vec2,
+fn sprite_affine(
+    position: ultraviolet::Vec2,
+    angle: f32,
     scale: ultraviolet::Vec2,
+    skew: ultraviolet::Vec2,
     source_size: ultraviolet::Vec2,
-) -> ultraviolet::Mat3 {
-    ultraviolet::Mat3::from_translation(ultraviolet::Vec2::new(
-        -source_size.x * origin.x * scale.x,
-        -source_size.y * origin.y * scale.y,
-    ))
+    origin: ultraviolet::Vec2,
+    custom_transform: Option<Affine2>,
+) -> Affine2 {
+    match custom_transform {
+        Some(custom_transform) => Affine2 {
+            x_axis: custom_transform.x_axis * source_size.x,
+            y_axis: custom_transform.y_axis * source_size.y,
+            translation: custom_transform.translation,
+        },
+        None => Affine2::from_sprite(position, angle, scale, skew, source_size, origin),
+    }
 }
-#[inline]
-fn calculate_rotation_mat(angle: f32) -> ultraviolet::Mat3 {
-    ultraviolet::Mat3::from_rotation_z(angle * std::f32::consts::PI / 180.0)
+
+/// Adds `range` to the dirty ranges of sprites, growing the last one if they touch.
+fn mark_range_dirty(dirty: &mut Vec<Range<usize>>, range: Range<usize>, length: usize) {
+    if let Some(last) = dirty.last_mut() {
+        if last.start <= range.start && range.start <= last.end {
+            last.end = last.end.max(range.end);
+            return;
+        }
+    }
+
+    if dirty.len() >= MAX_DIRTY_RANGES {
+        dirty.clear();
+        dirty.push(0..length.max(range.end));
+    } else {
+        dirty.push(range);
+    }
 }
-#[inline]
-fn calculate_translation_mat(position: ultraviolet::Vec2) -> ultraviolet::Mat3 {
-    ultraviolet::Mat3::from_translation(ultraviolet::Vec2::new((position).x, (position).y))
+
+/// Stable reference to a sprite stored in [`Sprites`].
+///
+/// Unlike a raw column index, a handle keeps pointing at the same sprite when
+/// other sprites are removed. Once its own sprite is removed the handle goes
+/// stale and every method taking it returns `None`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteHandle {
+    index: u32,
+    generation: u32,
+}
+
+/// Everything needed to add a sprite, see [`Sprites::add_desc`].
+///
+/// Start from [`SpriteDesc::new`] and override what differs from the defaults:
+///
+/// ```
+/// # use vulpo::backend::sprite::SpriteDesc;
+/// let desc = SpriteDesc::new((128, 128))
+///     .with_position(ultraviolet::Vec2::new(100.0, 50.0))
+///     .with_origin(ultraviolet::Vec2::new(0.5, 0.5));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpriteDesc {
+    pub texture_index: u32,
+    /// Top left corner of the source rectangle in texels.
+    pub source_position: ultraviolet::Vec2,
+    /// Size of the source rectangle in texels, also the size of the sprite at scale 1.
+    pub source_size: ultraviolet::Vec2,
+    pub position: ultraviolet::Vec2,
+    /// Counter-clockwise, in degrees.
+    pub angle: f32,
+    pub scale: ultraviolet::Vec2,
+    pub depth: f32,
+    pub color: [f32; 4],
+    /// Point the sprite is positioned, scaled and rotated around, relative to its size.
+    pub origin: ultraviolet::Vec2,
+    /// Mirror the texture horizontally, the quad itself stays where it is.
+    pub flip_x: bool,
+    /// Mirror the texture vertically, the quad itself stays where it is.
+    pub flip_y: bool,
+    /// Angles in degrees the sprite is sheared by along x and y, before it's rotated.
+    pub skew: ultraviolet::Vec2,
+    /// Replaces position, angle, scale, skew and origin, see [`Sprites::set_custom_transform`].
+    pub custom_transform: Option<Affine2>,
+}
+
+impl SpriteDesc {
+    /// The whole first texture of `texture_size` texels, untransformed at the world origin,
+    /// at scale 1, depth 0 and white.
+    pub fn new(texture_size: (u32, u32)) -> Self {
+        Self::from_source_rect(
+            ultraviolet::Vec2::zero(),
+            ultraviolet::Vec2::new(texture_size.0 as f32, texture_size.1 as f32),
+        )
+    }
+
+    /// Same as [`SpriteDesc::new`], but showing the source rectangle at `source_position`
+    /// of `source_size` texels, e.g. a region of an atlas or a frame of a sheet.
+    pub fn from_source_rect(
+        source_position: ultraviolet::Vec2,
+        source_size: ultraviolet::Vec2,
+    ) -> Self {
+        Self {
+            texture_index: 0,
+            source_position,
+            source_size,
+            position: ultraviolet::Vec2::zero(),
+            angle: 0.0,
+            scale: ultraviolet::Vec2::one(),
+            depth: 0.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            origin: ultraviolet::Vec2::zero(),
+            flip_x: false,
+            flip_y: false,
+            skew: ultraviolet::Vec2::zero(),
+            custom_transform: None,
+        }
+    }
+
+    pub fn with_texture_index(mut self, texture_index: u32) -> Self {
+        self.texture_index = texture_index;
+        self
+    }
+    pub fn with_source_rect(
+        mut self,
+        source_position: ultraviolet::Vec2,
+        source_size: ultraviolet::Vec2,
+    ) -> Self {
+        self.source_position = source_position;
+        self.source_size = source_size;
+        self
+    }
+    pub fn with_position(mut self, position: ultraviolet::Vec2) -> Self {
+        self.position = position;
+        self
+    }
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+    pub fn with_scale(mut self, scale: ultraviolet::Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn with_origin(mut self, origin: ultraviolet::Vec2) -> Self {
+        self.origin = origin;
+        self
+    }
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+    pub fn with_skew(mut self, skew: ultraviolet::Vec2) -> Self {
+        self.skew = skew;
+        self
+    }
+    pub fn with_custom_transform(mut self, custom_transform: Affine2) -> Self {
+        self.custom_transform = Some(custom_transform);
+        self
+    }
+}
+
+/// Consecutive sprites of a draw order that all use the same texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpriteBatch {
+    pub texture_index: u32,
+    /// Positions in the draw order, not sprite indices.
+    pub sprites: Range<usize>,
+}
+
+/// Index format that can address the vertices of `sprite_count` sprites, 16-bit up to
+/// [`MAX_U16_SPRITES`] and 32-bit past that.
+pub fn index_format_for(sprite_count: usize) -> wgpu::IndexFormat {
+    if sprite_count <= MAX_U16_SPRITES {
+        wgpu::IndexFormat::Uint16
+    } else {
+        wgpu::IndexFormat::Uint32
+    }
+}
+
+/// Size of a single index in bytes.
+pub fn index_size(format: wgpu::IndexFormat) -> usize {
+    match format {
+        wgpu::IndexFormat::Uint16 => std::mem::size_of::<u16>(),
+        wgpu::IndexFormat::Uint32 => std::mem::size_of::<u32>(),
+    }
+}
+
+/// Quad indices in either index format, ready to be written into an index buffer of
+/// the same format.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn with_capacity(format: wgpu::IndexFormat, capacity: usize) -> Self {
+        match format {
+            wgpu::IndexFormat::Uint16 => Indices::U16(Vec::with_capacity(capacity)),
+            wgpu::IndexFormat::Uint32 => Indices::U32(Vec::with_capacity(capacity)),
+        }
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Indices::U16(_) => wgpu::IndexFormat::Uint16,
+            Indices::U32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Indices::U16(indices) => bytemuck::cast_slice(indices),
+            Indices::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+
+    /// Appends the six indices of the quad of the sprite at `sprite_index`.
+    ///
+    /// Panics if 16-bit indices can't address its vertices, instead of wrapping around to
+    /// the quad of another sprite.
+    #[inline]
+    fn push_sprite(&mut self, sprite_index: usize) {
+        match self {
+            Indices::U16(indices) => {
+                assert!(
+                    sprite_index < MAX_U16_SPRITES,
+                    "16-bit indices can't address more than {} sprites",
+                    MAX_U16_SPRITES
+                );
+                indices.extend(
+                    BASELINE_INDICES
+                        .iter()
+                        .map(|baseline| *baseline + 4 * sprite_index as u16),
+                )
+            }
+            Indices::U32(indices) => indices.extend(
+                BASELINE_INDICES
+                    .iter()
+                    .map(|baseline| *baseline as u32 + 4 * sprite_index as u32),
+            ),
+        }
+    }
+}
+
+struct Slot {
+    dense: usize,
+    generation: u32,
+    alive: bool,
 }
 
 pub struct Sprites {
     length: usize,
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    handles: Vec<SpriteHandle>,
+    dirty: Vec<Range<usize>>,
+    reordered: bool,
+
     texture_index: Vec<u32>,
     source_position: Vec<ultraviolet::Vec2>,
     source_size: Vec<ultraviolet::Vec2>,
@@ -61,19 +404,29 @@ pub struct Sprites {
     depth: Vec<f32>,
     color: Vec<[f32; 4]>,
     origin: Vec<ultraviolet::Vec2>,
+    flip_x: Vec<bool>,
+    flip_y: Vec<bool>,
+    skew: Vec<ultraviolet::Vec2>,
+    custom_transform: Vec<Option<Affine2>>,
 
-    scale_mat: Vec<ultraviolet::Mat3>,
-    origin_translation_mat: Vec<ultraviolet::Mat3>,
-    rotation_mat: Vec<ultraviolet::Mat3>,
-    translation_mat: Vec<ultraviolet::Mat3>,
+    /// Cached from position, angle, scale, skew, source size, origin and custom transform.
+    /// Setters only flag it as stale, [`Sprites::update_transforms`] recomputes it.
+    transform: Vec<Affine2>,
+    transform_stale: Vec<bool>,
 
-    threads: usize,
+    uv_inset: f32,
+    parallelism: Parallelism,
 }
 
 impl Sprites {
     pub fn new() -> Self {
         Self {
             length: 0,
+            slots: vec![],
+            free_slots: vec![],
+            handles: vec![],
+            dirty: vec![],
+            reordered: false,
             texture_index: vec![],
             source_position: vec![],
             source_size: vec![],
@@ -83,13 +436,16 @@ impl Sprites {
             depth: vec![],
             color: vec![],
             origin: vec![],
+            flip_x: vec![],
+            flip_y: vec![],
+            skew: vec![],
+            custom_transform: vec![],
 
-            scale_mat: vec![],
-            origin_translation_mat: vec![],
-            rotation_mat: vec![],
-            translation_mat: vec![],
+            transform: vec![],
+            transform_stale: vec![],
 
-            threads: 16,
+            uv_inset: DEFAULT_UV_INSET,
+            parallelism: Parallelism::default(),
         }
     }
 
@@ -104,8 +460,34 @@ impl Sprites {
         depth: f32,
         color: [f32; 4],
         origin: ultraviolet::Vec2,
-    ) -> usize {
-        let index = self.texture_index.len();
+    ) -> SpriteHandle {
+        // Recycle a freed slot if there is one, its generation was already bumped on removal
+        let handle = match self.free_slots.pop() {
+            Some(slot_index) => {
+                let slot = &mut self.slots[slot_index as usize];
+                slot.dense = self.length;
+                slot.alive = true;
+                SpriteHandle {
+                    index: slot_index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    dense: self.length,
+                    generation: 0,
+                    alive: true,
+                });
+                SpriteHandle {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+        self.handles.push(handle);
+        self.mark_dirty(self.length);
+        self.reordered = true;
+
         self.length += 1;
         self.texture_index.push(texture_index);
         self.source_position.push(source_position);
@@ -116,219 +498,1591 @@ impl Sprites {
         self.depth.push(depth);
         self.color.push(color);
         self.origin.push(origin);
+        self.flip_x.push(false);
+        self.flip_y.push(false);
+        self.skew.push(ultraviolet::Vec2::zero());
+        self.custom_transform.push(None);
+
+        self.transform.push(Affine2::from_sprite(
+            position,
+            angle,
+            scale,
+            ultraviolet::Vec2::zero(),
+            source_size,
+            origin,
+        ));
+        self.transform_stale.push(false);
+
+        handle
+    }
+
+    pub fn add_desc(&mut self, desc: SpriteDesc) -> SpriteHandle {
+        let handle = self.add(
+            desc.texture_index,
+            desc.source_position,
+            desc.source_size,
+            desc.position,
+            desc.angle,
+            desc.scale,
+            desc.depth,
+            desc.color,
+            desc.origin,
+        );
+
+        // `add` leaves these at their defaults, the new sprite is the last one
+        let index = self.length - 1;
+        self.flip_x[index] = desc.flip_x;
+        self.flip_y[index] = desc.flip_y;
+        if desc.skew != ultraviolet::Vec2::zero() || desc.custom_transform.is_some() {
+            self.skew[index] = desc.skew;
+            self.custom_transform[index] = desc.custom_transform;
+            self.transform_stale[index] = true;
+        }
+
+        handle
+    }
 
-        self.scale_mat.push(calculate_scale_mat(scale, source_size));
-        self.origin_translation_mat
-            .push(calculate_origin_translation_mat(origin, scale, source_size));
-        self.rotation_mat.push(calculate_rotation_mat(angle));
-        self.translation_mat
-            .push(calculate_translation_mat(position));
+    /// Adds every sprite of `descs`, reserving room for all of them up front. Returns
+    /// their handles in the same order.
+    pub fn extend<I: IntoIterator<Item = SpriteDesc>>(&mut self, descs: I) -> Vec<SpriteHandle> {
+        let descs = descs.into_iter();
+        self.reserve(descs.size_hint().0);
 
-        index
+        descs.map(|desc| self.add_desc(desc)).collect()
     }
 
-    pub fn set_texture_index(&mut self, index: usize, val: u32) -> Option<()> {
+    /// Reserves room for at least `additional` more sprites in every column.
+    pub fn reserve(&mut self, additional: usize) {
+        self.handles.reserve(additional);
+        self.texture_index.reserve(additional);
+        self.source_position.reserve(additional);
+        self.source_size.reserve(additional);
+        self.position.reserve(additional);
+        self.angle.reserve(additional);
+        self.scale.reserve(additional);
+        self.depth.reserve(additional);
+        self.color.reserve(additional);
+        self.origin.reserve(additional);
+        self.flip_x.reserve(additional);
+        self.flip_y.reserve(additional);
+        self.skew.reserve(additional);
+        self.custom_transform.reserve(additional);
+        self.transform.reserve(additional);
+        self.transform_stale.reserve(additional);
+    }
+
+    /// Column index of a live sprite, `None` if the handle is stale.
+    fn dense_index(&self, handle: SpriteHandle) -> Option<usize> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.alive && slot.generation == handle.generation {
+            Some(slot.dense)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, handle: SpriteHandle) -> bool {
+        self.dense_index(handle).is_some()
+    }
+
+    pub fn set_texture_index(&mut self, handle: SpriteHandle, val: u32) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.texture_index.get_mut(index)?) = val;
+        self.mark_dirty(index);
+        self.reordered = true;
         Some(())
     }
-    pub fn set_source_position(&mut self, index: usize, val: ultraviolet::Vec2) -> Option<()> {
+    pub fn set_source_position(
+        &mut self,
+        handle: SpriteHandle,
+        val: ultraviolet::Vec2,
+    ) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.source_position.get_mut(index)?) = val;
+        self.mark_dirty(index);
         Some(())
     }
-    pub fn set_source_size(&mut self, index: usize, val: ultraviolet::Vec2) -> Option<()> {
+    pub fn set_source_size(&mut self, handle: SpriteHandle, val: ultraviolet::Vec2) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.source_size.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
 
-        // Recreate matrices
-        let scale = self.scale.get(index)?;
-        let origin = self.origin.get(index)?;
-        *(self.scale_mat.get_mut(index)?) = calculate_scale_mat(*scale, val);
-        *(self.origin_translation_mat.get_mut(index)?) =
-            calculate_origin_translation_mat(*origin, *scale, val);
-
+        self.mark_dirty(index);
         Some(())
     }
-    pub fn set_position(&mut self, index: usize, val: ultraviolet::Vec2) -> Option<()> {
+    pub fn set_position(&mut self, handle: SpriteHandle, val: ultraviolet::Vec2) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.position.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
 
-        // Recreate matrices
-        *(self.translation_mat.get_mut(index)?) = calculate_translation_mat(val);
-
+        self.mark_dirty(index);
         Some(())
     }
-    pub fn set_angle(&mut self, index: usize, val: f32) -> Option<()> {
+    pub fn set_angle(&mut self, handle: SpriteHandle, val: f32) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.angle.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
 
-        // Recreate matrices
-        *(self.rotation_mat.get_mut(index)?) = calculate_rotation_mat(val);
-
+        self.mark_dirty(index);
         Some(())
     }
-    pub fn set_scale(&mut self, index: usize, val: ultraviolet::Vec2) -> Option<()> {
+    pub fn set_scale(&mut self, handle: SpriteHandle, val: ultraviolet::Vec2) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.scale.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
 
-        // Recreate matrices
-        let origin = self.origin.get(index)?;
-        let source_size = self.source_size.get(index)?;
-        *(self.scale_mat.get_mut(index)?) = calculate_scale_mat(val, *source_size);
-        *(self.origin_translation_mat.get_mut(index)?) =
-            calculate_origin_translation_mat(*origin, val, *source_size);
-
+        self.mark_dirty(index);
         Some(())
     }
-    pub fn set_depth(&mut self, index: usize, val: f32) -> Option<()> {
+    /// Greater depth is drawn in front, anything outside of `-100.0..=100.0` is clipped.
+    pub fn set_depth(&mut self, handle: SpriteHandle, val: f32) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.depth.get_mut(index)?) = val;
+        self.mark_dirty(index);
+        self.reordered = true;
         Some(())
     }
-    pub fn set_color(&mut self, index: usize, val: [f32; 4]) -> Option<()> {
+    pub fn set_color(&mut self, handle: SpriteHandle, val: [f32; 4]) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.color.get_mut(index)?) = val;
+        self.mark_dirty(index);
         Some(())
     }
-    pub fn set_origin(&mut self, index: usize, val: ultraviolet::Vec2) -> Option<()> {
+    pub fn set_origin(&mut self, handle: SpriteHandle, val: ultraviolet::Vec2) -> Option<()> {
+        let index = self.dense_index(handle)?;
         *(self.origin.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
 
-        // Recreate matrices
-        let scale = self.scale.get(index)?;
-        let source_size = self.source_size.get(index)?;
-        *(self.origin_translation_mat.get_mut(index)?) =
-            calculate_origin_translation_mat(val, *scale, *source_size);
+        self.mark_dirty(index);
+        Some(())
+    }
+    pub fn set_flip_x(&mut self, handle: SpriteHandle, val: bool) -> Option<()> {
+        let index = self.dense_index(handle)?;
+        *(self.flip_x.get_mut(index)?) = val;
+        self.mark_dirty(index);
+        Some(())
+    }
+    pub fn set_flip_y(&mut self, handle: SpriteHandle, val: bool) -> Option<()> {
+        let index = self.dense_index(handle)?;
+        *(self.flip_y.get_mut(index)?) = val;
+        self.mark_dirty(index);
+        Some(())
+    }
+    /// Shears the sprite by `val.x` degrees along x and `val.y` degrees along y, around
+    /// its origin and before it's rotated.
+    pub fn set_skew(&mut self, handle: SpriteHandle, val: ultraviolet::Vec2) -> Option<()> {
+        let index = self.dense_index(handle)?;
+        *(self.skew.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
 
+        self.mark_dirty(index);
         Some(())
     }
+    /// Places the sprite with `val` instead of its position, angle, scale, skew and origin,
+    /// until it's set back to `None`. `val` maps the sprite's local space, `(0, 0)` to its
+    /// source size with y up, onto the screen; build it from a `Mat3` with `into()`.
+    pub fn set_custom_transform(
+        &mut self,
+        handle: SpriteHandle,
+        val: Option<Affine2>,
+    ) -> Option<()> {
+        let index = self.dense_index(handle)?;
+        *(self.custom_transform.get_mut(index)?) = val;
+        *(self.transform_stale.get_mut(index)?) = true;
+
+        self.mark_dirty(index);
+        Some(())
+    }
+
+    pub fn texture_index(&self, handle: SpriteHandle) -> Option<u32> {
+        Some(self.texture_index[self.dense_index(handle)?])
+    }
+    pub fn source_position(&self, handle: SpriteHandle) -> Option<ultraviolet::Vec2> {
+        Some(self.source_position[self.dense_index(handle)?])
+    }
+    pub fn source_size(&self, handle: SpriteHandle) -> Option<ultraviolet::Vec2> {
+        Some(self.source_size[self.dense_index(handle)?])
+    }
+    pub fn position(&self, handle: SpriteHandle) -> Option<ultraviolet::Vec2> {
+        Some(self.position[self.dense_index(handle)?])
+    }
+    pub fn angle(&self, handle: SpriteHandle) -> Option<f32> {
+        Some(self.angle[self.dense_index(handle)?])
+    }
+    pub fn scale(&self, handle: SpriteHandle) -> Option<ultraviolet::Vec2> {
+        Some(self.scale[self.dense_index(handle)?])
+    }
+    pub fn depth(&self, handle: SpriteHandle) -> Option<f32> {
+        Some(self.depth[self.dense_index(handle)?])
+    }
+    pub fn color(&self, handle: SpriteHandle) -> Option<[f32; 4]> {
+        Some(self.color[self.dense_index(handle)?])
+    }
+    pub fn origin(&self, handle: SpriteHandle) -> Option<ultraviolet::Vec2> {
+        Some(self.origin[self.dense_index(handle)?])
+    }
+    pub fn flip_x(&self, handle: SpriteHandle) -> Option<bool> {
+        Some(self.flip_x[self.dense_index(handle)?])
+    }
+    pub fn flip_y(&self, handle: SpriteHandle) -> Option<bool> {
+        Some(self.flip_y[self.dense_index(handle)?])
+    }
+    pub fn skew(&self, handle: SpriteHandle) -> Option<ultraviolet::Vec2> {
+        Some(self.skew[self.dense_index(handle)?])
+    }
+    pub fn custom_transform(&self, handle: SpriteHandle) -> Option<Option<Affine2>> {
+        Some(self.custom_transform[self.dense_index(handle)?])
+    }
+
+    pub fn get(&self, handle: SpriteHandle) -> Option<SpriteRef<'_>> {
+        Some(SpriteRef {
+            sprites: self,
+            index: self.dense_index(handle)?,
+        })
+    }
+    pub fn get_mut(&mut self, handle: SpriteHandle) -> Option<SpriteMut<'_>> {
+        let index = self.dense_index(handle)?;
+        Some(SpriteMut {
+            sprites: self,
+            handle,
+            index,
+        })
+    }
+
+    /// Every live sprite in storage order, which removals shuffle.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = SpriteRef<'_>> + '_ {
+        (0..self.length).map(move |index| SpriteRef {
+            sprites: self,
+            index,
+        })
+    }
+
+    // Columns in storage order: the same index in every column, including the handles,
+    // is the same sprite
+    pub fn handles(&self) -> &[SpriteHandle] {
+        &self.handles
+    }
+    pub fn texture_indices(&self) -> &[u32] {
+        &self.texture_index
+    }
+    pub fn source_positions(&self) -> &[ultraviolet::Vec2] {
+        &self.source_position
+    }
+    pub fn source_sizes(&self) -> &[ultraviolet::Vec2] {
+        &self.source_size
+    }
+    pub fn positions(&self) -> &[ultraviolet::Vec2] {
+        &self.position
+    }
+    pub fn angles(&self) -> &[f32] {
+        &self.angle
+    }
+    pub fn scales(&self) -> &[ultraviolet::Vec2] {
+        &self.scale
+    }
+    pub fn depths(&self) -> &[f32] {
+        &self.depth
+    }
+    pub fn colors(&self) -> &[[f32; 4]] {
+        &self.color
+    }
+    pub fn origins(&self) -> &[ultraviolet::Vec2] {
+        &self.origin
+    }
+    pub fn flip_xs(&self) -> &[bool] {
+        &self.flip_x
+    }
+    pub fn flip_ys(&self) -> &[bool] {
+        &self.flip_y
+    }
+    pub fn skews(&self) -> &[ultraviolet::Vec2] {
+        &self.skew
+    }
+    pub fn custom_transforms(&self) -> &[Option<Affine2>] {
+        &self.custom_transform
+    }
+
+    /// Mutable slices of the position, angle, scale and color of the sprites in `range`
+    /// (`0..len()` for all of them), for moving many sprites at once. Transforms and dirty
+    /// ranges are only updated once the returned guard is dropped.
+    pub fn columns_mut(&mut self, range: Range<usize>) -> ColumnsMut<'_> {
+        assert!(range.end <= self.length, "Sprite range out of bounds");
+
+        ColumnsMut {
+            positions: &mut self.position[range.clone()],
+            angles: &mut self.angle[range.clone()],
+            scales: &mut self.scale[range.clone()],
+            colors: &mut self.color[range.clone()],
+            source_sizes: &self.source_size[range.clone()],
+            origins: &self.origin[range.clone()],
+            skews: &self.skew[range.clone()],
+            custom_transforms: &self.custom_transform[range.clone()],
+            transforms: &mut self.transform[range.clone()],
+            transform_stale: &mut self.transform_stale[range.clone()],
+            dirty: &mut self.dirty,
+            length: self.length,
+            range,
+            parallelism: &self.parallelism,
+        }
+    }
 
     pub fn len(&self) -> usize {
         self.length
     }
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Shrinks every source rectangle by `texels` on each side when calculating UVs,
+    /// [`DEFAULT_UV_INSET`] unless changed.
+    ///
+    /// With nearest sampling (`Sampler::pixel`) fragments on the very edge of a sprite can
+    /// land exactly on the border of its source rectangle and pick up a texel of its
+    /// neighbour in the sheet, the default keeps them inside. Use `0.5` with linear
+    /// filtering so the filter never reaches across the border, or `0.0` to sample the
+    /// exact rectangle.
+    pub fn set_uv_inset(&mut self, texels: f32) {
+        self.uv_inset = texels;
+        self.mark_all_dirty();
+    }
+    pub fn uv_inset(&self) -> f32 {
+        self.uv_inset
+    }
+
+    /// Changes when and on which pool vertex generation, sorting and transform updates
+    /// run in parallel.
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+    pub fn parallelism(&self) -> &Parallelism {
+        &self.parallelism
+    }
+
+    fn mark_dirty(&mut self, index: usize) {
+        mark_range_dirty(&mut self.dirty, index..index + 1, self.length);
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty.clear();
+        self.dirty.push(0..self.length);
+    }
+
+    /// Whether any sprite was added, removed or modified since the last [`Sprites::clear_dirty`].
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+    /// Whether the draw order may have changed since the last [`Sprites::clear_dirty`],
+    /// i.e. sprites were added, removed or given a new depth or texture.
+    pub fn is_reordered(&self) -> bool {
+        self.reordered
+    }
+    pub fn clear_dirty(&mut self) {
+        self.dirty.clear();
+        self.reordered = false;
+    }
+
+    /// Sorted, merged ranges of sprite indices modified since the last [`Sprites::clear_dirty`].
+    /// Ranges are in sprites, not vertices.
+    pub fn dirty_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = self
+            .dirty
+            .iter()
+            .map(|range| range.start..range.end.min(self.length))
+            .filter(|range| !range.is_empty())
+            .collect::<Vec<_>>();
+        ranges.sort_unstable_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+
+    /// Removes a sprite in O(1) by moving the last sprite into its place.
+    ///
+    /// The columns stay dense, so the order of the remaining sprites is not
+    /// preserved. Their handles stay valid.
+    pub fn remove(&mut self, handle: SpriteHandle) -> Option<()> {
+        let index = self.dense_index(handle)?;
 
-    pub fn remove(&mut self, index: usize) {
         self.length -= 1;
-        self.texture_index.remove(index);
-        self.source_position.remove(index);
-        self.source_size.remove(index);
-        self.position.remove(index);
-        self.angle.remove(index);
-        self.scale.remove(index);
-        self.depth.remove(index);
-        self.color.remove(index);
-        self.origin.remove(index);
-
-        self.scale_mat.remove(index);
-        self.origin_translation_mat.remove(index);
-        self.rotation_mat.remove(index);
-        self.translation_mat.remove(index);
+        self.texture_index.swap_remove(index);
+        self.source_position.swap_remove(index);
+        self.source_size.swap_remove(index);
+        self.position.swap_remove(index);
+        self.angle.swap_remove(index);
+        self.scale.swap_remove(index);
+        self.depth.swap_remove(index);
+        self.color.swap_remove(index);
+        self.origin.swap_remove(index);
+        self.flip_x.swap_remove(index);
+        self.flip_y.swap_remove(index);
+        self.skew.swap_remove(index);
+        self.custom_transform.swap_remove(index);
+
+        self.transform.swap_remove(index);
+        self.transform_stale.swap_remove(index);
+
+        // Point the moved sprite's slot at its new place
+        self.handles.swap_remove(index);
+        if let Some(moved) = self.handles.get(index) {
+            self.slots[moved.index as usize].dense = index;
+            self.mark_dirty(index);
+        }
+
+        // Invalidate the handle and make its slot available for reuse
+        let slot = &mut self.slots[handle.index as usize];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(handle.index);
+        self.reordered = true;
+
+        Some(())
     }
 
-    pub fn vertices_indices(
+    /// Vertices of the sprites in `range` only, e.g. one of the [`Sprites::dirty_ranges`].
+    pub fn vertices(&self, range: Range<usize>, texture_sizes: &[(u32, u32)]) -> Vec<Vertex> {
+        let mut result_vertices = vec![Vertex::zeroed(); range.len() * 4];
+        self.write_vertices(range, texture_sizes, &mut result_vertices);
+
+        result_vertices
+    }
+
+    /// Indices of the quads in `range`. They only depend on the position of a
+    /// sprite, not on its contents, so an index buffer filled with the indices of
+    /// every quad it has room for never has to be written again.
+    ///
+    /// Panics if `format` can't address every quad of the range, see [`index_format_for`].
+    pub fn indices(range: Range<usize>, format: wgpu::IndexFormat) -> Indices {
+        let mut result_indices = Indices::with_capacity(format, range.len() * 6);
+        for sprite_index in range {
+            result_indices.push_sprite(sprite_index);
+        }
+
+        result_indices
+    }
+
+    /// Index format able to address the vertices of every current sprite.
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        index_format_for(self.length)
+    }
+
+    /// Sprite indices sorted back to front, from the lowest depth to the highest.
+    ///
+    /// Sprites of equal depth keep their storage order, which removals shuffle, so give
+    /// overlapping translucent sprites distinct depths.
+    pub fn back_to_front(&self) -> Vec<usize> {
+        let mut order = (0..self.length).collect::<Vec<_>>();
+        let compare = |a: &usize, b: &usize| {
+            self.depth[*a]
+                .partial_cmp(&self.depth[*b])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        };
+        if self.parallelism.is_parallel(self.length) {
+            self.parallelism.install(|| order.par_sort_by(compare));
+        } else {
+            order.sort_by(compare);
+        }
+
+        order
+    }
+
+    /// Sprite indices grouped by texture index, sprites sharing a texture keep their
+    /// storage order.
+    pub fn by_texture(&self) -> Vec<usize> {
+        let mut order = (0..self.length).collect::<Vec<_>>();
+        let key = |sprite_index: &usize| self.texture_index[*sprite_index];
+        if self.parallelism.is_parallel(self.length) {
+            self.parallelism.install(|| order.par_sort_by_key(key));
+        } else {
+            order.sort_by_key(key);
+        }
+
+        order
+    }
+
+    /// Splits a draw order into runs of consecutive sprites sharing a texture, each of
+    /// which can be drawn with a single call.
+    pub fn batches(&self, order: &[usize]) -> Vec<SpriteBatch> {
+        let mut batches: Vec<SpriteBatch> = vec![];
+        for (position, sprite_index) in order.iter().enumerate() {
+            let texture_index = self.texture_index[*sprite_index];
+            match batches.last_mut() {
+                Some(batch) if batch.texture_index == texture_index => batch.sprites.end += 1,
+                _ => batches.push(SpriteBatch {
+                    texture_index,
+                    sprites: position..position + 1,
+                }),
+            }
+        }
+
+        batches
+    }
+
+    /// Indices of the quads in `order`, e.g. [`Sprites::back_to_front`], in that order.
+    ///
+    /// Panics if `format` can't address every quad of the order, see [`index_format_for`].
+    pub fn ordered_indices(order: &[usize], format: wgpu::IndexFormat) -> Indices {
+        let mut result_indices = Indices::with_capacity(format, order.len() * 6);
+        for sprite_index in order {
+            result_indices.push_sprite(*sprite_index);
+        }
+
+        result_indices
+    }
+
+    /// Recomputes the cached transforms of the sprites whose position, angle, scale, skew,
+    /// source size, origin or custom transform changed since the last call. Vertex generation works without it,
+    /// but then calculates the transforms of those sprites over again every time.
+    pub fn update_transforms(&mut self) {
+        let position = &self.position;
+        let angle = &self.angle;
+        let scale = &self.scale;
+        let source_size = &self.source_size;
+        let origin = &self.origin;
+        let skew = &self.skew;
+        let custom_transform = &self.custom_transform;
+        let update = |sprite_index: usize, (transform, stale): (&mut Affine2, &mut bool)| {
+            if *stale {
+                *transform = sprite_affine(
+                    position[sprite_index],
+                    angle[sprite_index],
+                    scale[sprite_index],
+                    skew[sprite_index],
+                    source_size[sprite_index],
+                    origin[sprite_index],
+                    custom_transform[sprite_index],
+                );
+                *stale = false;
+            }
+        };
+
+        if self.parallelism.is_parallel(self.length) {
+            let chunk_sprites = self.parallelism.chunk_sprites(self.length);
+            let transform = &mut self.transform;
+            let transform_stale = &mut self.transform_stale;
+            self.parallelism.install(|| {
+                transform
+                    .par_iter_mut()
+                    .zip(transform_stale.par_iter_mut())
+                    .with_min_len(chunk_sprites)
+                    .enumerate()
+                    .for_each(|(sprite_index, sprite)| update(sprite_index, sprite));
+            });
+        } else {
+            self.transform
+                .iter_mut()
+                .zip(self.transform_stale.iter_mut())
+                .enumerate()
+                .for_each(|(sprite_index, sprite)| update(sprite_index, sprite));
+        }
+    }
+
+    #[inline]
+    fn sprite_transform(&self, sprite_index: usize) -> Affine2 {
+        if self.transform_stale[sprite_index] {
+            sprite_affine(
+                self.position[sprite_index],
+                self.angle[sprite_index],
+                self.scale[sprite_index],
+                self.skew[sprite_index],
+                self.source_size[sprite_index],
+                self.origin[sprite_index],
+                self.custom_transform[sprite_index],
+            )
+        } else {
+            self.transform[sprite_index]
+        }
+    }
+
+    /// Source rectangle in texture coordinates (minimum and size), shrunk by the inset
+    /// on every side.
+    #[inline]
+    fn source_tex_rect(
         &self,
-        texture_width: u32,
-        texture_height: u32,
-    ) -> (Vec<Vertex>, Vec<u16>) {
-        let thread_count = self.threads;
-        let chunk_size = self.length / thread_count;
-        let leftover = self.length - (chunk_size * thread_count);
-
-        let mut result_vertices = vec![];
-        let mut result_indices = vec![];
-
-        (0..thread_count)
-            .into_par_iter()
-            .map(|index| {
-                let thread_slice_start = if index == 0 {
-                    0
-                } else {
-                    leftover + chunk_size * index
-                };
-                let thread_slice_range = if index == 0 {
-                    thread_slice_start..thread_slice_start + chunk_size + leftover
-                } else {
-                    thread_slice_start..thread_slice_start + chunk_size
+        sprite_index: usize,
+        texture_sizes: &[(u32, u32)],
+    ) -> (ultraviolet::Vec2, ultraviolet::Vec2) {
+        let texture_index = self.texture_index[sprite_index] as usize;
+        let (texture_width, texture_height) =
+            texture_sizes.get(texture_index).unwrap_or_else(|| {
+                panic!(
+                    "Sprite uses texture {} but there are only {} texture sizes",
+                    texture_index,
+                    texture_sizes.len()
+                )
+            });
+        let texture_size = ultraviolet::Vec2::new(*texture_width as f32, *texture_height as f32);
+        let inset = ultraviolet::Vec2::broadcast(self.uv_inset);
+
+        (
+            (self.source_position[sprite_index] + inset) / texture_size,
+            (self.source_size[sprite_index] - inset * 2.0) / texture_size,
+        )
+    }
+
+    /// [`Sprites::source_tex_rect`] with flips applied. Flipped axes start at the far edge
+    /// and have a negative size, so the UVs run backwards while the quad keeps its winding.
+    #[inline]
+    fn tex_rect(
+        &self,
+        sprite_index: usize,
+        texture_sizes: &[(u32, u32)],
+    ) -> (ultraviolet::Vec2, ultraviolet::Vec2) {
+        let (mut src_relative_min, mut src_relative_size) =
+            self.source_tex_rect(sprite_index, texture_sizes);
+        if self.flip_x[sprite_index] {
+            src_relative_min.x += src_relative_size.x;
+            src_relative_size.x = -src_relative_size.x;
+        }
+        if self.flip_y[sprite_index] {
+            src_relative_min.y += src_relative_size.y;
+            src_relative_size.y = -src_relative_size.y;
+        }
+
+        (src_relative_min, src_relative_size)
+    }
+
+    /// Raw transform columns of a sprite, the vertex shader does the math. Flips go along
+    /// unapplied as well, the shader mirrors the UVs itself.
+    #[inline]
+    fn sprite_instance(&self, sprite_index: usize, texture_sizes: &[(u32, u32)]) -> SpriteInstance {
+        let (src_relative_min, src_relative_size) =
+            self.source_tex_rect(sprite_index, texture_sizes);
+        let custom_transform = self.custom_transform[sprite_index];
+        let custom = custom_transform.unwrap_or(Affine2::IDENTITY);
+
+        SpriteInstance {
+            position: *self.position[sprite_index].as_array(),
+            scale: *self.scale[sprite_index].as_array(),
+            origin: *self.origin[sprite_index].as_array(),
+            source_size: *self.source_size[sprite_index].as_array(),
+            tex_rect: [
+                src_relative_min.x,
+                src_relative_min.y,
+                src_relative_size.x,
+                src_relative_size.y,
+            ],
+            color: self.color[sprite_index],
+            angle: self.angle[sprite_index],
+            depth: self.depth[sprite_index],
+            skew: *self.skew[sprite_index].as_array(),
+            flip: [
+                self.flip_x[sprite_index] as u8 as f32,
+                self.flip_y[sprite_index] as u8 as f32,
+            ],
+            custom_x_axis: *custom.x_axis.as_array(),
+            custom_y_axis: *custom.y_axis.as_array(),
+            custom_translation: *custom.translation.as_array(),
+            custom_transform: custom_transform.is_some() as u8 as f32,
+            texture_index: self.texture_index[sprite_index],
+        }
+    }
+
+    /// Instances of the sprites in `range` for the instanced path, which transforms them
+    /// in the vertex shader instead of generating four vertices each.
+    ///
+    /// Panics if a sprite's texture index has no size in `texture_sizes`.
+    pub fn instances(
+        &self,
+        range: Range<usize>,
+        texture_sizes: &[(u32, u32)],
+    ) -> Vec<SpriteInstance> {
+        if !self.parallelism.is_parallel(range.len()) {
+            return range
+                .map(|sprite_index| self.sprite_instance(sprite_index, texture_sizes))
+                .collect();
+        }
+
+        let chunk_sprites = self.parallelism.chunk_sprites(range.len());
+        self.parallelism.install(|| {
+            range
+                .into_par_iter()
+                .with_min_len(chunk_sprites)
+                .map(|sprite_index| self.sprite_instance(sprite_index, texture_sizes))
+                .collect()
+        })
+    }
+
+    /// Instances of the sprites in `order`, e.g. [`Sprites::back_to_front`], in that order.
+    pub fn ordered_instances(
+        &self,
+        order: &[usize],
+        texture_sizes: &[(u32, u32)],
+    ) -> Vec<SpriteInstance> {
+        if !self.parallelism.is_parallel(order.len()) {
+            return order
+                .iter()
+                .map(|sprite_index| self.sprite_instance(*sprite_index, texture_sizes))
+                .collect();
+        }
+
+        let chunk_sprites = self.parallelism.chunk_sprites(order.len());
+        self.parallelism.install(|| {
+            order
+                .par_iter()
+                .with_min_len(chunk_sprites)
+                .map(|sprite_index| self.sprite_instance(*sprite_index, texture_sizes))
+                .collect()
+        })
+    }
+
+    /// Vertices of `LANES` consecutive sprites starting at `first`, transformed together.
+    ///
+    /// Corners are calculated with the same operations in the same order as
+    /// [`Sprites::sprite_vertices`], so both agree up to float rounding.
+    #[inline]
+    fn wide_sprite_vertices(
+        &self,
+        first: usize,
+        texture_sizes: &[(u32, u32)],
+        vertices: &mut [Vertex],
+    ) {
+        // Gather the sprites into lanes
+        let mut mirroring = [false; LANES];
+        let mut x_axis = [ultraviolet::Vec2::zero(); LANES];
+        let mut y_axis = [ultraviolet::Vec2::zero(); LANES];
+        let mut translation = [ultraviolet::Vec2::zero(); LANES];
+        let mut src_relative_min = [ultraviolet::Vec2::zero(); LANES];
+        let mut src_relative_size = [ultraviolet::Vec2::zero(); LANES];
+        for lane in 0..LANES {
+            let transform = self.sprite_transform(first + lane);
+            mirroring[lane] = transform.is_mirroring();
+            x_axis[lane] = transform.x_axis;
+            y_axis[lane] = transform.y_axis;
+            translation[lane] = transform.translation;
+            let (min, size) = self.tex_rect(first + lane, texture_sizes);
+            src_relative_min[lane] = min;
+            src_relative_size[lane] = size;
+        }
+        let x_axis = ultraviolet::Vec2x8::from(x_axis);
+        let y_axis = ultraviolet::Vec2x8::from(y_axis);
+        let translation = ultraviolet::Vec2x8::from(translation);
+        let src_relative_min = ultraviolet::Vec2x8::from(src_relative_min);
+        let src_relative_size = ultraviolet::Vec2x8::from(src_relative_size);
+
+        let corners = [
+            (BASE_VEC_A, BASE_UV_A),
+            (BASE_VEC_B, BASE_UV_B),
+            (BASE_VEC_C, BASE_UV_C),
+            (BASE_VEC_D, BASE_UV_D),
+        ];
+        for (corner, (base_vec, base_uv)) in corners.iter().enumerate() {
+            let positions: [ultraviolet::Vec2; LANES] = (x_axis
+                * ultraviolet::f32x8::splat(base_vec.x)
+                + y_axis * ultraviolet::f32x8::splat(base_vec.y)
+                + translation)
+                .into();
+            let uvs: [ultraviolet::Vec2; LANES] = (src_relative_min
+                + ultraviolet::Vec2x8::splat(*base_uv) * src_relative_size)
+                .into();
+
+            // Scatter the lanes back into their quads
+            for lane in 0..LANES {
+                vertices[lane * 4 + corner] = Vertex {
+                    position: [
+                        positions[lane].x,
+                        positions[lane].y,
+                        self.depth[first + lane],
+                    ],
+                    tex_coords: [uvs[lane].x, uvs[lane].y],
+                    color: self.color[first + lane],
                 };
-                let thread_source_positions = &self.source_position[thread_slice_range.clone()];
-                let thread_source_size = &self.source_size[thread_slice_range.clone()];
-                let thread_scale_mat = &self.scale_mat[thread_slice_range.clone()];
-                let thread_origin_translation_mat =
-                    &self.origin_translation_mat[thread_slice_range.clone()];
-                let thread_rotation_mat = &self.rotation_mat[thread_slice_range.clone()];
-                let thread_translation = &self.translation_mat[thread_slice_range.clone()];
-
-                let range = 0..thread_slice_range.len();
-                let mut result_vertices = vec![];
-                let mut result_indices = vec![];
-
-                for local_index in range {
-                    let source_position = thread_source_positions.get(local_index).unwrap().clone();
-                    let source_size = thread_source_size.get(local_index).unwrap().clone();
-
-                    let scale_mat = thread_scale_mat.get(local_index).unwrap().clone();
-                    let origin_translation_mat = thread_origin_translation_mat
-                        .get(local_index)
-                        .unwrap()
-                        .clone();
-                    let rotation_mat = thread_rotation_mat.get(local_index).unwrap().clone();
-                    let translation_mat = thread_translation.get(local_index).unwrap().clone();
-
-                    // Relative texture coordinates
-                    /*
-                    let _src_relative_min_x: f32 = source_position.x / texture_width as f32;
-                    let _src_relative_min_y: f32 = source_position.y / texture_height as f32;
-                    let _src_relative_max_x: f32 =
-                        source_position.x + source_size.x / texture_width as f32;
-                    let _src_relative_max_y: f32 =
-                        source_position.y + source_size.y / texture_height as f32;
-                    */
-
-                    // Transform matrix
-                    let transformation =
-                        translation_mat * rotation_mat * origin_translation_mat * scale_mat;
-
-                    // Calculate the position vectors
-                    let vec_a = transformation * BASE_VEC_A;
-                    let vec_b = transformation * BASE_VEC_B;
-                    let vec_c = transformation * BASE_VEC_C;
-                    let vec_d = transformation * BASE_VEC_D;
-
-                    // Create the UV arrays
-                    let uv_a = BASE_UV_A;
-                    let uv_b = BASE_UV_B;
-                    let uv_c = BASE_UV_C;
-                    let uv_d = BASE_UV_D;
-
-                    // Calculate the indices
-                    let indices = BASELINE_INDICES
-                        .iter()
-                        .map(|i| *i + (4 * (thread_slice_start as u16 + local_index as u16)))
-                        .collect::<Vec<_>>();
-
-                    // Generate the vertices
-                    let vertices = vec![
-                        Vertex {
-                            position: [vec_a.x, vec_a.y, vec_a.z],
-                            tex_coords: [uv_a.x, uv_a.y],
-                        }, // A
-                        Vertex {
-                            position: [vec_b.x, vec_b.y, vec_b.z],
-                            tex_coords: [uv_b.x, uv_b.y],
-                        }, // B
-                        Vertex {
-                            position: [vec_c.x, vec_c.y, vec_c.z],
-                            tex_coords: [uv_c.x, uv_c.y],
-                        }, // C
-                        Vertex {
-                            position: [vec_d.x, vec_d.y, vec_d.z],
-                            tex_coords: [uv_d.x, uv_d.y],
-                        }, // D
-                    ];
-
-                    result_vertices.extend_from_slice(&vertices[..]);
-                    result_indices.extend_from_slice(&indices[..]);
-                }
+            }
+        }
+
+        for (lane, mirroring) in mirroring.iter().enumerate() {
+            if *mirroring {
+                vertices.swap(lane * 4 + 1, lane * 4 + 3);
+            }
+        }
+    }
+
+    #[inline]
+    fn sprite_vertices(&self, sprite_index: usize, texture_sizes: &[(u32, u32)]) -> [Vertex; 4] {
+        let transform = self.sprite_transform(sprite_index);
+        let (src_relative_min, src_relative_size) = self.tex_rect(sprite_index, texture_sizes);
+
+        // Calculate the position vectors
+        let vec_a = transform.transform_point(BASE_VEC_A);
+        let vec_b = transform.transform_point(BASE_VEC_B);
+        let vec_c = transform.transform_point(BASE_VEC_C);
+        let vec_d = transform.transform_point(BASE_VEC_D);
+
+        // Create the UV arrays
+        let uv_a = src_relative_min + BASE_UV_A * src_relative_size;
+        let uv_b = src_relative_min + BASE_UV_B * src_relative_size;
+        let uv_c = src_relative_min + BASE_UV_C * src_relative_size;
+        let uv_d = src_relative_min + BASE_UV_D * src_relative_size;
+
+        let depth = self.depth[sprite_index];
+        let color = self.color[sprite_index];
+
+        // Generate the vertices
+        let mut vertices = [
+            Vertex {
+                position: [vec_a.x, vec_a.y, depth],
+                tex_coords: [uv_a.x, uv_a.y],
+                color,
+            }, // A
+            Vertex {
+                position: [vec_b.x, vec_b.y, depth],
+                tex_coords: [uv_b.x, uv_b.y],
+                color,
+            }, // B
+            Vertex {
+                position: [vec_c.x, vec_c.y, depth],
+                tex_coords: [uv_c.x, uv_c.y],
+                color,
+            }, // C
+            Vertex {
+                position: [vec_d.x, vec_d.y, depth],
+                tex_coords: [uv_d.x, uv_d.y],
+                color,
+            }, // D
+        ];
+
+        // Mirrored quads trade B and D, so both triangles stay front facing
+        if transform.is_mirroring() {
+            vertices.swap(1, 3);
+        }
+
+        vertices
+    }
+
+    /// Same as [`Sprites::write_vertices`], but transforms `LANES` sprites at a time with
+    /// `ultraviolet`'s wide types. Sprites that don't fill a whole lane at the end of a
+    /// chunk go through the scalar path.
+    ///
+    /// Only the corners are transformed in lanes, the transforms and source rectangles are
+    /// still gathered sprite by sprite. That doesn't beat the scalar path yet (see
+    /// `benches/sprite.rs`), so nothing uses it by default.
+    pub fn write_vertices_wide(
+        &self,
+        range: Range<usize>,
+        texture_sizes: &[(u32, u32)],
+        vertices: &mut [Vertex],
+    ) {
+        self.for_each_vertex_chunk(range, vertices, |mut sprite_index, chunk| {
+            let mut lanes = chunk.chunks_exact_mut(LANES * 4);
+            for lane_vertices in &mut lanes {
+                self.wide_sprite_vertices(sprite_index, texture_sizes, lane_vertices);
+                sprite_index += LANES;
+            }
+            for (quad, sprite_index) in lanes
+                .into_remainder()
+                .chunks_exact_mut(4)
+                .zip(sprite_index..)
+            {
+                quad.copy_from_slice(&self.sprite_vertices(sprite_index, texture_sizes));
+            }
+        });
+    }
+
+    /// Vertices and storage-order indices of every sprite. `texture_sizes` holds the
+    /// size of every texture, looked up by the sprites' texture index. Panics if a sprite
+    /// uses a texture index past its end.
+    ///
+    /// `index_format` has to be able to address every vertex, see [`Sprites::index_format`].
+    pub fn vertices_indices(
+        &self,
+        texture_sizes: &[(u32, u32)],
+        index_format: wgpu::IndexFormat,
+    ) -> (Vec<Vertex>, Indices) {
+        assert!(
+            index_format == wgpu::IndexFormat::Uint32 || self.length <= MAX_U16_SPRITES,
+            "16-bit indices can't address more than {} sprites",
+            MAX_U16_SPRITES
+        );
+
+        let mut result_vertices = vec![Vertex::zeroed(); self.length * 4];
+        self.write_vertices(0..self.length, texture_sizes, &mut result_vertices);
+
+        (result_vertices, Self::indices(0..self.length, index_format))
+    }
+
+    /// Writes the four vertices of every sprite in `range` into `vertices`, which has to
+    /// hold exactly `4 * range.len()` of them. Nothing is allocated, so `vertices` can be
+    /// reused every frame or be a mapped buffer.
+    ///
+    /// Large ranges are split into chunks as configured by [`Sprites::set_parallelism`],
+    /// each writing its own part of `vertices`.
+    ///
+    /// Panics if a sprite's texture index has no size in `texture_sizes`.
+    pub fn write_vertices(
+        &self,
+        range: Range<usize>,
+        texture_sizes: &[(u32, u32)],
+        vertices: &mut [Vertex],
+    ) {
+        self.for_each_vertex_chunk(range, vertices, |chunk_start, chunk| {
+            for (quad, sprite_index) in chunk.chunks_exact_mut(4).zip(chunk_start..) {
+                quad.copy_from_slice(&self.sprite_vertices(sprite_index, texture_sizes));
+            }
+        });
+    }
+
+    /// Splits `vertices` into chunks of whole lanes of sprites and calls `write_chunk` with
+    /// the index of the first sprite of each, on the calling thread for small ranges.
+    fn for_each_vertex_chunk<F>(&self, range: Range<usize>, vertices: &mut [Vertex], write_chunk: F)
+    where
+        F: Fn(usize, &mut [Vertex]) + Sync,
+    {
+        assert_eq!(
+            vertices.len(),
+            range.len() * 4,
+            "Vertex slice doesn't fit the sprite range"
+        );
+        if range.is_empty() {
+            return;
+        }
+        if !self.parallelism.is_parallel(range.len()) {
+            write_chunk(range.start, vertices);
+            return;
+        }
+
+        let chunk_sprites = self.parallelism.chunk_sprites(range.len()).div_ceil(LANES) * LANES;
+        self.parallelism.install(|| {
+            vertices
+                .par_chunks_mut(chunk_sprites * 4)
+                .enumerate()
+                .for_each(|(chunk_index, chunk)| {
+                    write_chunk(range.start + chunk_index * chunk_sprites, chunk)
+                });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_at(sprites: &mut Sprites, x: f32) -> SpriteHandle {
+        sprites.add(
+            0,
+            ultraviolet::Vec2::new(0.0, 0.0),
+            ultraviolet::Vec2::new(16.0, 16.0),
+            ultraviolet::Vec2::new(x, 0.0),
+            0.0,
+            ultraviolet::Vec2::new(1.0, 1.0),
+            1.0,
+            [1.0, 1.0, 1.0, 1.0],
+            ultraviolet::Vec2::new(0.0, 0.0),
+        )
+    }
 
-                (result_vertices, result_indices)
-            })
-            .unzip_into_vecs(&mut result_vertices, &mut result_indices);
+    #[test]
+    fn handles_survive_removal_of_other_sprites() {
+        let mut sprites = Sprites::new();
+        let a = add_at(&mut sprites, 0.0);
+        let b = add_at(&mut sprites, 1.0);
+        let c = add_at(&mut sprites, 2.0);
 
-        let result_vertices = result_vertices.into_iter().flatten().collect();
-        let result_indices = result_indices.into_iter().flatten().collect();
+        sprites.remove(a).unwrap();
+        sprites
+            .set_position(c, ultraviolet::Vec2::new(42.0, 0.0))
+            .unwrap();
+
+        assert_eq!(sprites.len(), 2);
+        assert!(sprites.contains(b));
+        assert_eq!(sprites.position[sprites.dense_index(b).unwrap()].x, 1.0);
+        assert_eq!(sprites.position[sprites.dense_index(c).unwrap()].x, 42.0);
+    }
+
+    #[test]
+    fn stale_handles_are_rejected() {
+        let mut sprites = Sprites::new();
+        let a = add_at(&mut sprites, 0.0);
+        sprites.remove(a).unwrap();
+
+        assert!(!sprites.contains(a));
+        assert_eq!(sprites.remove(a), None);
+        assert_eq!(sprites.set_angle(a, 90.0), None);
+    }
+
+    #[test]
+    fn freed_slots_are_reused_with_a_new_generation() {
+        let mut sprites = Sprites::new();
+        let a = add_at(&mut sprites, 0.0);
+        let b = add_at(&mut sprites, 1.0);
+        sprites.remove(a).unwrap();
+        let c = add_at(&mut sprites, 2.0);
+
+        assert_eq!(sprites.slots.len(), 2);
+        assert_ne!(a, c);
+        assert!(!sprites.contains(a));
+        assert_eq!(sprites.position[sprites.dense_index(b).unwrap()].x, 1.0);
+        assert_eq!(sprites.position[sprites.dense_index(c).unwrap()].x, 2.0);
+    }
+
+    #[test]
+    fn uvs_cover_the_source_rectangle() {
+        let mut sprites = Sprites::new();
+        let handle = add_at(&mut sprites, 0.0);
+        sprites.set_uv_inset(0.0);
+        sprites
+            .set_source_position(handle, ultraviolet::Vec2::new(16.0, 32.0))
+            .unwrap();
+
+        let uvs = sprites
+            .vertices(0..1, &[(64, 64)])
+            .iter()
+            .map(|vertex| vertex.tex_coords)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            uvs,
+            vec![[0.25, 0.5], [0.25, 0.75], [0.5, 0.75], [0.5, 0.5]]
+        );
+
+        sprites.set_uv_inset(0.5);
+        let uv_a = sprites.vertices(0..1, &[(64, 64)])[0].tex_coords;
+        assert_eq!(uv_a, [16.5 / 64.0, 32.5 / 64.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Sprite uses texture 2 but there are only 2 texture sizes")]
+    fn texture_indices_without_a_size_are_rejected() {
+        let mut sprites = Sprites::new();
+        let handle = add_at(&mut sprites, 0.0);
+        sprites.set_texture_index(handle, 2).unwrap();
+        sprites.vertices(0..1, &[(16, 16), (32, 32)]);
+    }
+
+    #[test]
+    fn default_uvs_stay_inside_the_source_rectangle_with_nearest_sampling() {
+        let mut sprites = Sprites::new();
+        for flip in 0..4 {
+            let handle = add_at(&mut sprites, 0.0);
+            sprites
+                .set_source_position(handle, ultraviolet::Vec2::new(16.0, 32.0))
+                .unwrap();
+            sprites.set_flip_x(handle, flip & 1 != 0).unwrap();
+            sprites.set_flip_y(handle, flip & 2 != 0).unwrap();
+        }
+
+        // Corners bound every UV a fragment can get, nearest sampling picks the texel
+        // they fall in. Texels 16 to 31 and 32 to 47 belong to the sprites
+        for texture_size in [64, 4096].iter() {
+            let scale = *texture_size as f32;
+            for vertex in sprites.vertices(0..4, &[(*texture_size, *texture_size)]) {
+                let texel_x = (vertex.tex_coords[0] * scale).floor();
+                let texel_y = (vertex.tex_coords[1] * scale).floor();
+                assert!((16.0..=31.0).contains(&texel_x), "{:?}", vertex.tex_coords);
+                assert!((32.0..=47.0).contains(&texel_y), "{:?}", vertex.tex_coords);
+            }
+        }
+    }
+
+    #[test]
+    fn flips_swap_uvs_and_keep_the_quad() {
+        let mut sprites = Sprites::new();
+        let handle = add_at(&mut sprites, 0.0);
+        let unflipped = sprites.vertices(0..1, &[(16, 16)]);
+
+        sprites.set_flip_x(handle, true).unwrap();
+        let flipped = sprites.vertices(0..1, &[(16, 16)]);
+        for (flipped, unflipped) in flipped.iter().zip(&unflipped) {
+            assert_eq!(flipped.position, unflipped.position);
+        }
+        // A and D trade UVs, as do B and C
+        assert_eq!(flipped[0].tex_coords, unflipped[3].tex_coords);
+        assert_eq!(flipped[1].tex_coords, unflipped[2].tex_coords);
+
+        sprites.set_flip_y(handle, true).unwrap();
+        let flipped = sprites.vertices(0..1, &[(16, 16)]);
+        assert_eq!(flipped[0].tex_coords, unflipped[2].tex_coords);
+        assert_eq!(sprites.flip_xs(), &[true]);
+        assert_eq!(sprites.flip_ys(), &[true]);
+    }
+
+    #[test]
+    fn mirrored_quads_stay_front_facing() {
+        let mut sprites = Sprites::new();
+        for i in 0..LANES {
+            let handle = add_at(&mut sprites, i as f32);
+            let scale = [(-1.0, 1.0), (1.0, -1.0), (-1.0, -1.0), (1.0, 1.0)][i % 4];
+            sprites
+                .set_scale(handle, ultraviolet::Vec2::new(scale.0, scale.1))
+                .unwrap();
+        }
+
+        let mut wide = vec![Vertex::zeroed(); LANES * 4];
+        sprites.write_vertices_wide(0..LANES, &[(16, 16)], &mut wide);
+        for vertices in [sprites.vertices(0..LANES, &[(16, 16)]), wide].iter() {
+            for (quad, triangle) in vertices
+                .chunks_exact(4)
+                .flat_map(|quad| BASELINE_INDICES.chunks_exact(3).map(move |t| (quad, t)))
+            {
+                let a = quad[triangle[0] as usize].position;
+                let b = quad[triangle[1] as usize].position;
+                let c = quad[triangle[2] as usize].position;
+                let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+                assert!(area > 0.0, "Clockwise triangle in {:?}", quad);
+            }
+        }
+    }
+
+    #[test]
+    fn skew_and_custom_transforms_move_the_corners() {
+        let mut sprites = Sprites::new();
+        let handle = add_at(&mut sprites, 0.0);
+
+        // 45 degrees along x leans the top edge over by the sprite's height
+        sprites
+            .set_skew(handle, ultraviolet::Vec2::new(45.0, 0.0))
+            .unwrap();
+        let vertex_a = sprites.vertices(0..1, &[(16, 16)])[0].position;
+        assert!((vertex_a[0] - 16.0).abs() < 1e-4);
+        assert!((vertex_a[1] - 16.0).abs() < 1e-4);
+
+        // The custom transform wins over the rest, in the sprite's local texels
+        let custom_transform =
+            ultraviolet::Mat3::from_translation(ultraviolet::Vec2::new(5.0, 6.0))
+                * ultraviolet::Mat3::from_nonuniform_scale_homogeneous(ultraviolet::Vec2::new(
+                    -1.0, 2.0,
+                ));
+        sprites
+            .set_custom_transform(handle, Some(custom_transform.into()))
+            .unwrap();
+        sprites.update_transforms();
+        // The transform mirrors, so B and D trade places
+        let vertices = sprites.vertices(0..1, &[(16, 16)]);
+        assert_eq!(vertices[3].position[..2], [5.0, 6.0]);
+        assert_eq!(vertices[1].position[..2], [-11.0, 38.0]);
+
+        sprites.set_custom_transform(handle, None).unwrap();
+        let vertex_b = sprites.vertices(0..1, &[(16, 16)])[1].position;
+        assert_eq!(vertex_b[..2], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn back_to_front_follows_depth() {
+        let mut sprites = Sprites::new();
+        let handles = (0..4)
+            .map(|i| add_at(&mut sprites, i as f32))
+            .collect::<Vec<_>>();
+        sprites.clear_dirty();
+
+        sprites.set_depth(handles[0], 10.0).unwrap();
+        sprites.set_depth(handles[2], -5.0).unwrap();
+        assert!(sprites.is_reordered());
+        assert_eq!(sprites.back_to_front(), vec![2, 1, 3, 0]);
+        assert_eq!(sprites.vertices(2..3, &[(16, 16)])[0].position[2], -5.0);
+        assert_eq!(
+            Sprites::ordered_indices(&[2, 0], wgpu::IndexFormat::Uint16),
+            Indices::U16(vec![8, 9, 11, 11, 9, 10, 0, 1, 3, 3, 1, 2])
+        );
+    }
+
+    #[test]
+    fn sprites_are_batched_by_texture() {
+        let mut sprites = Sprites::new();
+        let handles = (0..5)
+            .map(|i| add_at(&mut sprites, i as f32))
+            .collect::<Vec<_>>();
+        sprites.set_texture_index(handles[1], 1).unwrap();
+        sprites.set_texture_index(handles[3], 1).unwrap();
+
+        let order = sprites.by_texture();
+        assert_eq!(order, vec![0, 2, 4, 1, 3]);
+        assert_eq!(
+            sprites.batches(&order),
+            vec![
+                SpriteBatch {
+                    texture_index: 0,
+                    sprites: 0..3
+                },
+                SpriteBatch {
+                    texture_index: 1,
+                    sprites: 3..5
+                },
+            ]
+        );
+
+        // Each sprite gets the UVs of its own texture
+        sprites.set_uv_inset(0.0);
+        let vertices = sprites.vertices(0..2, &[(16, 16), (32, 32)]);
+        assert_eq!(vertices[2].tex_coords, [1.0, 1.0]);
+        assert_eq!(vertices[6].tex_coords, [0.5, 0.5]);
+    }
+
+    #[test]
+    fn dirty_ranges_are_merged_and_clamped() {
+        let mut sprites = Sprites::new();
+        let handles = (0..8)
+            .map(|i| add_at(&mut sprites, i as f32))
+            .collect::<Vec<_>>();
+        sprites.clear_dirty();
+
+        sprites.set_angle(handles[5], 45.0).unwrap();
+        sprites.set_angle(handles[1], 45.0).unwrap();
+        sprites.set_angle(handles[2], 45.0).unwrap();
+        sprites.set_angle(handles[6], 45.0).unwrap();
+        sprites.set_angle(handles[7], 45.0).unwrap();
+        sprites.remove(handles[7]).unwrap();
+
+        assert_eq!(sprites.dirty_ranges(), vec![1..3, 5..7]);
+
+        // Only the dirty quads are regenerated, and they match a full rebuild
+        let (all_vertices, _) = sprites.vertices_indices(&[(16, 16)], sprites.index_format());
+        let dirty_vertices = sprites.vertices(5..7, &[(16, 16)]);
+        assert_eq!(
+            all_vertices[5 * 4..7 * 4]
+                .iter()
+                .map(|vertex| vertex.position)
+                .collect::<Vec<_>>(),
+            dirty_vertices
+                .iter()
+                .map(|vertex| vertex.position)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn indices_switch_to_u32_past_the_u16_limit() {
+        let mut sprites = Sprites::new();
+        for i in 0..MAX_U16_SPRITES {
+            add_at(&mut sprites, i as f32);
+        }
+        assert_eq!(sprites.index_format(), wgpu::IndexFormat::Uint16);
+
+        add_at(&mut sprites, 0.0);
+        assert_eq!(sprites.index_format(), wgpu::IndexFormat::Uint32);
+
+        let (_, indices) = sprites.vertices_indices(&[(16, 16)], sprites.index_format());
+        let last_quad = 4 * MAX_U16_SPRITES as u32;
+        match indices {
+            Indices::U32(indices) => assert_eq!(
+                indices[indices.len() - 6..],
+                [
+                    last_quad,
+                    last_quad + 1,
+                    last_quad + 3,
+                    last_quad + 3,
+                    last_quad + 1,
+                    last_quad + 2
+                ]
+            ),
+            Indices::U16(_) => panic!("Expected 32-bit indices"),
+        }
+    }
+
+    /// Position and UV of a vertex of an instance the way `sprite_instanced.vert`
+    /// calculates them.
+    fn shader_vertex(
+        instance: &SpriteInstance,
+        vertex_index: usize,
+    ) -> (ultraviolet::Vec2, ultraviolet::Vec2) {
+        let vec2 = |array: [f32; 2]| ultraviolet::Vec2::new(array[0], array[1]);
+        let transform = if instance.custom_transform > 0.5 {
+            // mat2(custom_x_axis, custom_y_axis) * diag(source_size), column by column
+            let source_size = vec2(instance.source_size);
+            Affine2 {
+                x_axis: vec2(instance.custom_x_axis) * source_size.x,
+                y_axis: vec2(instance.custom_y_axis) * source_size.y,
+                translation: vec2(instance.custom_translation),
+            }
+        } else {
+            let size = vec2(instance.source_size) * vec2(instance.scale);
+            let skew_x = instance.skew[0].to_radians().tan();
+            let skew_y = instance.skew[1].to_radians().tan();
+            let (sin, cos) = instance.angle.to_radians().sin_cos();
+            // rotation * shear * scale, column by column
+            let x_axis = ultraviolet::Vec2::new(cos - sin * skew_y, sin + cos * skew_y) * size.x;
+            let y_axis = ultraviolet::Vec2::new(cos * skew_x - sin, sin * skew_x + cos) * size.y;
+            let origin = vec2(instance.origin);
+            Affine2 {
+                x_axis,
+                y_axis,
+                translation: vec2(instance.position) - x_axis * origin.x - y_axis * origin.y,
+            }
+        };
+
+        let corners = [BASE_VEC_A, BASE_VEC_B, BASE_VEC_C, BASE_VEC_D];
+        let mut quad = [0, 1, 2, 3];
+        if transform.is_mirroring() {
+            quad.swap(1, 3);
+        }
+        let corner = corners[quad[BASELINE_INDICES[vertex_index] as usize]];
+
+        let mut uv = ultraviolet::Vec2::new(corner.x, 1.0 - corner.y);
+        for axis in 0..2 {
+            if instance.flip[axis] > 0.5 {
+                uv[axis] = 1.0 - uv[axis];
+            }
+        }
+        let tex_rect = instance.tex_rect;
+        let uv = ultraviolet::Vec2::new(tex_rect[0], tex_rect[1])
+            + uv * ultraviolet::Vec2::new(tex_rect[2], tex_rect[3]);
+
+        (transform.transform_point(corner), uv)
+    }
+
+    #[test]
+    #[should_panic(expected = "16-bit indices can't address")]
+    fn u16_indices_past_the_limit_are_rejected() {
+        // The first quad past the limit would wrap around onto the first sprite
+        Sprites::ordered_indices(&[0, MAX_U16_SPRITES], wgpu::IndexFormat::Uint16);
+    }
+
+    #[test]
+    fn instances_match_the_vertices() {
+        let mut sprites = Sprites::new();
+        let handles = (0..5)
+            .map(|i| add_at(&mut sprites, i as f32))
+            .collect::<Vec<_>>();
+        sprites.set_angle(handles[1], 30.0).unwrap();
+        sprites
+            .set_source_position(handles[2], ultraviolet::Vec2::new(4.0, 8.0))
+            .unwrap();
+        sprites
+            .set_skew(handles[1], ultraviolet::Vec2::new(10.0, -20.0))
+            .unwrap();
+        sprites.set_flip_x(handles[1], true).unwrap();
+        sprites
+            .set_origin(handles[1], ultraviolet::Vec2::new(0.5, 0.25))
+            .unwrap();
+        let custom_transform =
+            ultraviolet::Mat3::from_translation(ultraviolet::Vec2::new(3.0, 4.0))
+                * ultraviolet::Mat3::from_rotation_z(0.5);
+        sprites
+            .set_custom_transform(handles[3], Some(custom_transform.into()))
+            .unwrap();
+        sprites.set_flip_y(handles[3], true).unwrap();
+        sprites
+            .set_scale(handles[4], ultraviolet::Vec2::new(-1.0, 1.0))
+            .unwrap();
+
+        let instances = sprites.ordered_instances(&[2, 1], &[(16, 16)]);
+        assert_eq!(instances[0].position, [2.0, 0.0]);
+        assert_eq!(instances[1].angle, 30.0);
+        assert_eq!(instances[1].skew, [10.0, -20.0]);
+        assert_eq!(instances[1].flip, [1.0, 0.0]);
+
+        // Transformed in the shader, vertices land where the CPU path puts them, mirrored
+        // quads included
+        let instances = sprites.instances(0..5, &[(16, 16)]);
+        let vertices = sprites.vertices(0..5, &[(16, 16)]);
+        for (instance, quad) in instances.iter().zip(vertices.chunks_exact(4)) {
+            for (vertex_index, baseline) in BASELINE_INDICES.iter().enumerate() {
+                let vertex = &quad[*baseline as usize];
+                let (world, uv) = shader_vertex(instance, vertex_index);
+                assert!((world.x - vertex.position[0]).abs() < 1e-4);
+                assert!((world.y - vertex.position[1]).abs() < 1e-4);
+                assert!((uv.x - vertex.tex_coords[0]).abs() < 1e-6);
+                assert!((uv.y - vertex.tex_coords[1]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn vertices_are_written_in_place_across_chunks() {
+        let mut sprites = Sprites::new();
+        for i in 0..600 {
+            add_at(&mut sprites, i as f32);
+        }
+        sprites.set_parallelism(Parallelism {
+            sequential_threshold: 0,
+            ..Parallelism::default()
+        });
+
+        let mut vertices = vec![Vertex::zeroed(); 590 * 4];
+        sprites.write_vertices(5..595, &[(16, 16)], &mut vertices);
+        for (quad, sprite_index) in vertices.chunks_exact(4).zip(5..595) {
+            let expected = sprites.sprite_vertices(sprite_index, &[(16, 16)]);
+            assert_eq!(
+                quad.iter()
+                    .map(|vertex| vertex.position)
+                    .collect::<Vec<_>>(),
+                expected
+                    .iter()
+                    .map(|vertex| vertex.position)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn transforms_are_recomputed_lazily() {
+        let mut sprites = Sprites::new();
+        let handle = add_at(&mut sprites, 10.0);
+        sprites.set_angle(handle, 90.0).unwrap();
+        sprites
+            .set_origin(handle, ultraviolet::Vec2::new(0.5, 0.5))
+            .unwrap();
+        assert!(sprites.transform_stale[0]);
+
+        // Same as translation * rotation * origin translation * scale
+        let matrix = ultraviolet::Mat3::from_translation(ultraviolet::Vec2::new(10.0, 0.0))
+            * ultraviolet::Mat3::from_rotation_z(std::f32::consts::FRAC_PI_2)
+            * ultraviolet::Mat3::from_translation(ultraviolet::Vec2::new(-8.0, -8.0))
+            * ultraviolet::Mat3::from_nonuniform_scale(ultraviolet::Vec3::new(16.0, 16.0, 1.0));
+        let expected = matrix * ultraviolet::Vec3::new(1.0, 0.0, 1.0);
+        let stale_vertex = sprites.vertices(0..1, &[(16, 16)])[2];
+        assert!((stale_vertex.position[0] - expected.x).abs() < 1e-4);
+        assert!((stale_vertex.position[1] - expected.y).abs() < 1e-4);
+
+        sprites.update_transforms();
+        assert!(!sprites.transform_stale[0]);
+        assert_eq!(
+            sprites.vertices(0..1, &[(16, 16)])[2].position,
+            stale_vertex.position
+        );
+    }
+
+    /// Small xorshift generator, good enough to scatter test sprites around.
+    fn random(state: &mut u32) -> f32 {
+        *state ^= *state << 13;
+        *state ^= *state >> 17;
+        *state ^= *state << 5;
+        *state as f32 / u32::MAX as f32
+    }
+
+    fn random_vec(state: &mut u32, scale: f32) -> ultraviolet::Vec2 {
+        ultraviolet::Vec2::new(random(state), random(state)) * scale
+    }
+
+    #[test]
+    fn wide_vertices_match_scalar_vertices() {
+        let mut state = 0x9e37_79b9;
+        let mut sprites = Sprites::new();
+        for _ in 0..1000 {
+            sprites.add(
+                0,
+                random_vec(&mut state, 128.0),
+                random_vec(&mut state, 64.0),
+                random_vec(&mut state, 1920.0),
+                random(&mut state) * 720.0 - 360.0,
+                random_vec(&mut state, 4.0) - ultraviolet::Vec2::broadcast(2.0),
+                random(&mut state) * 200.0 - 100.0,
+                [
+                    random(&mut state),
+                    random(&mut state),
+                    random(&mut state),
+                    1.0,
+                ],
+                random_vec(&mut state, 1.0),
+            );
+        }
+        sprites.set_uv_inset(0.5);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        sprites.set_parallelism(Parallelism {
+            sequential_threshold: 0,
+            ..Parallelism::with_pool(std::sync::Arc::new(pool))
+        });
+
+        // Odd bounds so the last chunk ends in a partial lane
+        let range = 3..998;
+        let mut scalar = vec![Vertex::zeroed(); range.len() * 4];
+        let mut wide = vec![Vertex::zeroed(); range.len() * 4];
+        sprites.write_vertices(range.clone(), &[(256, 256)], &mut scalar);
+        sprites.write_vertices_wide(range, &[(256, 256)], &mut wide);
+
+        for (scalar, wide) in scalar.iter().zip(&wide) {
+            let scalar_values = scalar.position.iter().chain(&scalar.tex_coords);
+            let wide_values = wide.position.iter().chain(&wide.tex_coords);
+            for (scalar, wide) in scalar_values.zip(wide_values) {
+                assert!((scalar - wide).abs() <= 1e-4 * scalar.abs().max(1.0));
+            }
+            assert_eq!(scalar.color, wide.color);
+        }
+    }
+
+    #[test]
+    fn descs_fill_in_defaults() {
+        let mut sprites = Sprites::new();
+        let handles = sprites.extend((0..3).map(|i| {
+            SpriteDesc::new((16, 16))
+                .with_position(ultraviolet::Vec2::new(i as f32, 0.0))
+                .with_depth(i as f32)
+        }));
+        assert_eq!(sprites.len(), 3);
+        assert!(handles.iter().all(|handle| sprites.contains(*handle)));
+
+        // Same as adding the defaults one by one
+        let handle = add_at(&mut sprites, 2.0);
+        sprites.set_depth(handle, 2.0).unwrap();
+        let vertices = sprites.vertices(2..4, &[(16, 16)]);
+        for (desc_vertex, added_vertex) in vertices[..4].iter().zip(&vertices[4..]) {
+            assert_eq!(desc_vertex.position, added_vertex.position);
+            assert_eq!(desc_vertex.tex_coords, added_vertex.tex_coords);
+            assert_eq!(desc_vertex.color, added_vertex.color);
+        }
+    }
+
+    #[test]
+    fn sprites_can_be_read_back() {
+        let mut sprites = Sprites::new();
+        let handles = (0..3)
+            .map(|i| add_at(&mut sprites, i as f32))
+            .collect::<Vec<_>>();
+        sprites.remove(handles[0]).unwrap();
+
+        assert_eq!(sprites.position(handles[0]), None);
+        assert_eq!(
+            sprites.position(handles[2]),
+            Some(ultraviolet::Vec2::new(2.0, 0.0))
+        );
+
+        let mut sprite = sprites.get_mut(handles[1]).unwrap();
+        sprite.set_angle(45.0);
+        assert_eq!(sprite.angle(), 45.0);
+        assert!(sprites.is_dirty());
+
+        // Views and columns line up in storage order
+        let positions = sprites
+            .iter()
+            .map(|sprite| sprite.position().x)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![2.0, 1.0]);
+        assert_eq!(sprites.handles(), &[handles[2], handles[1]]);
+        assert_eq!(sprites.angles(), &[0.0, 45.0]);
+    }
+
+    #[test]
+    fn column_guards_update_transforms_and_dirty_ranges_on_drop() {
+        let mut sprites = Sprites::new();
+        for i in 0..3000 {
+            add_at(&mut sprites, i as f32);
+        }
+        sprites.clear_dirty();
+
+        {
+            let mut columns = sprites.columns_mut(1000..3000);
+            columns.par_iter_mut().for_each(|(position, angle, _, _)| {
+                position.y += 10.0;
+                *angle = 90.0;
+            });
+        }
 
-        (result_vertices, result_indices)
+        assert_eq!(sprites.dirty_ranges(), vec![1000..3000]);
+        assert_eq!(
+            sprites.positions()[2000],
+            ultraviolet::Vec2::new(2000.0, 10.0)
+        );
+        assert!(!sprites.transform_stale[2000]);
+        assert_eq!(
+            sprites.transform[2000],
+            Affine2::from_sprite(
+                ultraviolet::Vec2::new(2000.0, 10.0),
+                90.0,
+                ultraviolet::Vec2::one(),
+                ultraviolet::Vec2::zero(),
+                ultraviolet::Vec2::new(16.0, 16.0),
+                ultraviolet::Vec2::zero(),
+            )
+        );
+        assert_eq!(
+            sprites.transform[999].translation,
+            ultraviolet::Vec2::new(999.0, 0.0)
+        );
     }
 }
@@ -0,0 +1,121 @@
+use crate::backend::parallelism::Parallelism;
+use crate::backend::sprite::{mark_range_dirty, sprite_affine, Affine2};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use std::ops::Range;
+
+/// Mutable slices of the position, angle, scale and color columns of a range of
+/// sprites, see [`crate::backend::sprite::Sprites::columns_mut`].
+///
+/// Index `i` of every slice is sprite `range.start + i` in storage order. Once the guard
+/// is dropped the transforms of the whole range are recomputed and it's marked dirty,
+/// instead of once per changed value like the setters do.
+pub struct ColumnsMut<'a> {
+    pub positions: &'a mut [ultraviolet::Vec2],
+    pub angles: &'a mut [f32],
+    pub scales: &'a mut [ultraviolet::Vec2],
+    pub colors: &'a mut [[f32; 4]],
+
+    pub(super) source_sizes: &'a [ultraviolet::Vec2],
+    pub(super) origins: &'a [ultraviolet::Vec2],
+    pub(super) skews: &'a [ultraviolet::Vec2],
+    pub(super) custom_transforms: &'a [Option<Affine2>],
+    pub(super) transforms: &'a mut [Affine2],
+    pub(super) transform_stale: &'a mut [bool],
+    pub(super) dirty: &'a mut Vec<Range<usize>>,
+    pub(super) length: usize,
+    pub(super) range: Range<usize>,
+    pub(super) parallelism: &'a Parallelism,
+}
+
+impl<'a> ColumnsMut<'a> {
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Position, angle, scale and color of every sprite in the range.
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl Iterator<
+        Item = (
+            &mut ultraviolet::Vec2,
+            &mut f32,
+            &mut ultraviolet::Vec2,
+            &mut [f32; 4],
+        ),
+    > {
+        self.positions
+            .iter_mut()
+            .zip(self.angles.iter_mut())
+            .zip(self.scales.iter_mut())
+            .zip(self.colors.iter_mut())
+            .map(|(((position, angle), scale), color)| (position, angle, scale, color))
+    }
+
+    /// Parallel version of [`ColumnsMut::iter_mut`], for rayon's pools.
+    pub fn par_iter_mut(
+        &mut self,
+    ) -> impl IndexedParallelIterator<
+        Item = (
+            &mut ultraviolet::Vec2,
+            &mut f32,
+            &mut ultraviolet::Vec2,
+            &mut [f32; 4],
+        ),
+    > {
+        self.positions
+            .par_iter_mut()
+            .zip(self.angles.par_iter_mut())
+            .zip(self.scales.par_iter_mut())
+            .zip(self.colors.par_iter_mut())
+            .map(|(((position, angle), scale), color)| (position, angle, scale, color))
+    }
+}
+
+impl<'a> Drop for ColumnsMut<'a> {
+    fn drop(&mut self) {
+        let positions = &*self.positions;
+        let angles = &*self.angles;
+        let scales = &*self.scales;
+        let source_sizes = self.source_sizes;
+        let origins = self.origins;
+        let skews = self.skews;
+        let custom_transforms = self.custom_transforms;
+        let recompute = |offset: usize, (transform, stale): (&mut Affine2, &mut bool)| {
+            *transform = sprite_affine(
+                positions[offset],
+                angles[offset],
+                scales[offset],
+                skews[offset],
+                source_sizes[offset],
+                origins[offset],
+                custom_transforms[offset],
+            );
+            *stale = false;
+        };
+
+        if self.parallelism.is_parallel(self.range.len()) {
+            let chunk_sprites = self.parallelism.chunk_sprites(self.range.len());
+            let transforms = &mut *self.transforms;
+            let transform_stale = &mut *self.transform_stale;
+            self.parallelism.install(|| {
+                transforms
+                    .par_iter_mut()
+                    .zip(transform_stale.par_iter_mut())
+                    .with_min_len(chunk_sprites)
+                    .enumerate()
+                    .for_each(|(offset, sprite)| recompute(offset, sprite));
+            });
+        } else {
+            self.transforms
+                .iter_mut()
+                .zip(self.transform_stale.iter_mut())
+                .enumerate()
+                .for_each(|(offset, sprite)| recompute(offset, sprite));
+        }
+
+        mark_range_dirty(self.dirty, self.range.clone(), self.length);
+    }
+}
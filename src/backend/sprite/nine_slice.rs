@@ -0,0 +1,324 @@
+use crate::backend::sprite::{SpriteDesc, SpriteHandle, Sprites};
+use std::convert::TryInto;
+
+/// Widths of the borders of a [`NineSlice`] in texels, measured inwards from the edges
+/// of its source rectangle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Borders {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl Borders {
+    /// The same width on every side.
+    pub fn uniform(width: f32) -> Self {
+        Self {
+            left: width,
+            right: width,
+            top: width,
+            bottom: width,
+        }
+    }
+}
+
+/// A resizable sprite made of nine sprites, e.g. a UI panel or button.
+///
+/// The source rectangle is cut into a 3x3 grid along the borders. Corners keep their
+/// size, the edges stretch along one axis and the center along both, so the borders
+/// stay crisp at any size. With `Sampler::pixel`, keep position, size and borders at
+/// whole pixels and leave [`Sprites::set_uv_inset`] at its default so the pieces don't
+/// pick up texels of their neighbours.
+///
+/// The pieces are ordinary sprites: they're batched, sorted and drawn like any other.
+/// Nine-slices are axis-aligned and can't be rotated, skewed or flipped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NineSlice {
+    /// Row by row from the top left, like the texture.
+    handles: [SpriteHandle; 9],
+    source_position: ultraviolet::Vec2,
+    source_size: ultraviolet::Vec2,
+    borders: Borders,
+    position: ultraviolet::Vec2,
+    size: ultraviolet::Vec2,
+}
+
+impl NineSlice {
+    /// Adds the pieces of a nine-slice `size` pixels large, with its bottom left corner
+    /// at `desc.position`. Texture, source rectangle, depth and color come from `desc` too,
+    /// the rest of it is ignored.
+    pub fn new(
+        sprites: &mut Sprites,
+        desc: SpriteDesc,
+        borders: Borders,
+        size: ultraviolet::Vec2,
+    ) -> Self {
+        let piece = SpriteDesc::from_source_rect(desc.source_position, desc.source_size)
+            .with_texture_index(desc.texture_index)
+            .with_depth(desc.depth)
+            .with_color(desc.color);
+        let handles = sprites.extend(vec![piece; 9]);
+        let nine_slice = Self {
+            handles: handles.try_into().unwrap(),
+            source_position: desc.source_position,
+            source_size: desc.source_size,
+            borders,
+            position: desc.position,
+            size,
+        };
+        nine_slice.layout(sprites);
+
+        nine_slice
+    }
+
+    pub fn handles(&self) -> &[SpriteHandle; 9] {
+        &self.handles
+    }
+    pub fn borders(&self) -> Borders {
+        self.borders
+    }
+    pub fn position(&self) -> ultraviolet::Vec2 {
+        self.position
+    }
+    pub fn size(&self) -> ultraviolet::Vec2 {
+        self.size
+    }
+
+    pub fn set_position(&mut self, sprites: &mut Sprites, val: ultraviolet::Vec2) -> Option<()> {
+        self.position = val;
+        self.layout(sprites)
+    }
+    /// Borders that don't fit into `val` are shrunk, keeping their ratio.
+    pub fn set_size(&mut self, sprites: &mut Sprites, val: ultraviolet::Vec2) -> Option<()> {
+        self.size = val;
+        self.layout(sprites)
+    }
+    /// Borders that don't fit into the source rectangle or the size are shrunk there,
+    /// keeping their ratio.
+    pub fn set_borders(&mut self, sprites: &mut Sprites, val: Borders) -> Option<()> {
+        self.borders = val;
+        self.layout(sprites)
+    }
+    pub fn set_source_rect(
+        &mut self,
+        sprites: &mut Sprites,
+        source_position: ultraviolet::Vec2,
+        source_size: ultraviolet::Vec2,
+    ) -> Option<()> {
+        self.source_position = source_position;
+        self.source_size = source_size;
+        self.layout(sprites)
+    }
+    pub fn set_texture_index(&self, sprites: &mut Sprites, val: u32) -> Option<()> {
+        for handle in &self.handles {
+            sprites.set_texture_index(*handle, val)?;
+        }
+        Some(())
+    }
+    pub fn set_depth(&self, sprites: &mut Sprites, val: f32) -> Option<()> {
+        for handle in &self.handles {
+            sprites.set_depth(*handle, val)?;
+        }
+        Some(())
+    }
+    pub fn set_color(&self, sprites: &mut Sprites, val: [f32; 4]) -> Option<()> {
+        for handle in &self.handles {
+            sprites.set_color(*handle, val)?;
+        }
+        Some(())
+    }
+
+    /// Removes all nine pieces, `None` if any of them was removed already.
+    pub fn remove(self, sprites: &mut Sprites) -> Option<()> {
+        let mut removed = Some(());
+        // Keep going past stale handles so the other pieces don't leak
+        for handle in &self.handles {
+            if sprites.remove(*handle).is_none() {
+                removed = None;
+            }
+        }
+        removed
+    }
+
+    /// Cuts the source rectangle along the borders and stretches every piece over its
+    /// part of the nine-slice. Borders are shrunk on either side to what fits.
+    fn layout(&self, sprites: &mut Sprites) -> Option<()> {
+        // Texture rows run down, screen rows up, so the top border is the last screen row
+        let (source_left, source_right) =
+            fit(self.borders.left, self.borders.right, self.source_size.x);
+        let (source_top, source_bottom) =
+            fit(self.borders.top, self.borders.bottom, self.source_size.y);
+        let source_columns = cuts(
+            self.source_position.x,
+            self.source_size.x,
+            source_left,
+            source_right,
+        );
+        let source_rows = cuts(
+            self.source_position.y,
+            self.source_size.y,
+            source_top,
+            source_bottom,
+        );
+        let (left, right) = fit(self.borders.left, self.borders.right, self.size.x);
+        let (bottom, top) = fit(self.borders.bottom, self.borders.top, self.size.y);
+        let columns = cuts(self.position.x, self.size.x, left, right);
+        let rows = cuts(self.position.y, self.size.y, bottom, top);
+
+        for (piece, handle) in self.handles.iter().enumerate() {
+            let (column, row) = (piece % 3, piece / 3);
+            let source_position = ultraviolet::Vec2::new(source_columns[column], source_rows[row]);
+            let source_size = ultraviolet::Vec2::new(
+                source_columns[column + 1] - source_columns[column],
+                source_rows[row + 1] - source_rows[row],
+            );
+            let position = ultraviolet::Vec2::new(columns[column], rows[2 - row]);
+            let size = ultraviolet::Vec2::new(
+                columns[column + 1] - columns[column],
+                rows[3 - row] - rows[2 - row],
+            );
+
+            sprites.set_source_position(*handle, source_position)?;
+            sprites.set_source_size(*handle, source_size)?;
+            sprites.set_position(*handle, position)?;
+            sprites.set_scale(*handle, stretch(size, source_size))?;
+        }
+
+        Some(())
+    }
+}
+
+/// Edges of the three pieces of a span from `start` that is `length` long.
+fn cuts(start: f32, length: f32, near: f32, far: f32) -> [f32; 4] {
+    [start, start + near, start + length - far, start + length]
+}
+
+/// Borders shrunk to fit into `length` if they're wider together.
+fn fit(near: f32, far: f32, length: f32) -> (f32, f32) {
+    let total = near + far;
+    if total > length && total > 0.0 {
+        let ratio = length.max(0.0) / total;
+        (near * ratio, far * ratio)
+    } else {
+        (near, far)
+    }
+}
+
+/// Scale that stretches `source_size` to `size`, zero for pieces cut down to nothing.
+fn stretch(size: ultraviolet::Vec2, source_size: ultraviolet::Vec2) -> ultraviolet::Vec2 {
+    let axis = |size: f32, source_size: f32| {
+        if source_size > 0.0 {
+            size / source_size
+        } else {
+            0.0
+        }
+    };
+
+    ultraviolet::Vec2::new(axis(size.x, source_size.x), axis(size.y, source_size.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::vertex::bounds;
+
+    #[test]
+    fn nine_slices_keep_their_corners() {
+        let mut sprites = Sprites::new();
+        let desc = SpriteDesc::new((64, 64))
+            .with_source_rect(
+                ultraviolet::Vec2::new(32.0, 0.0),
+                ultraviolet::Vec2::new(32.0, 32.0),
+            )
+            .with_position(ultraviolet::Vec2::new(10.0, 20.0));
+        let mut nine_slice = NineSlice::new(
+            &mut sprites,
+            desc,
+            Borders::uniform(8.0),
+            ultraviolet::Vec2::new(100.0, 60.0),
+        );
+        sprites.update_transforms();
+
+        // Top left corner at its own size, center stretched over the rest
+        let top_left = sprites.get(nine_slice.handles()[0]).unwrap();
+        assert_eq!(
+            top_left.source_position(),
+            ultraviolet::Vec2::new(32.0, 0.0)
+        );
+        assert_eq!(top_left.source_size(), ultraviolet::Vec2::new(8.0, 8.0));
+        assert_eq!(top_left.position(), ultraviolet::Vec2::new(10.0, 72.0));
+        assert_eq!(top_left.scale(), ultraviolet::Vec2::one());
+        let center = sprites.get(nine_slice.handles()[4]).unwrap();
+        assert_eq!(
+            center.scale(),
+            ultraviolet::Vec2::new(84.0 / 16.0, 44.0 / 16.0)
+        );
+
+        let vertices = sprites.vertices(0..9, &[(64, 64)]);
+        assert_eq!(bounds(&vertices), ([10.0, 20.0], [110.0, 80.0]));
+
+        // Too small for the borders, which shrink and leave no room for the center
+        nine_slice
+            .set_size(&mut sprites, ultraviolet::Vec2::new(8.0, 60.0))
+            .unwrap();
+        let center = sprites.get(nine_slice.handles()[4]).unwrap();
+        assert_eq!(center.scale().x, 0.0);
+        let left = sprites.get(nine_slice.handles()[3]).unwrap();
+        assert_eq!(left.scale().x, 0.5);
+    }
+
+    #[test]
+    fn borders_wider_than_the_source_are_fitted_into_it() {
+        let mut sprites = Sprites::new();
+        let desc = SpriteDesc::new((64, 64)).with_source_rect(
+            ultraviolet::Vec2::new(0.0, 0.0),
+            ultraviolet::Vec2::new(16.0, 8.0),
+        );
+        let borders = Borders {
+            left: 12.0,
+            right: 12.0,
+            top: 2.0,
+            bottom: 6.0,
+        };
+        let nine_slice = NineSlice::new(
+            &mut sprites,
+            desc,
+            borders,
+            ultraviolet::Vec2::new(64.0, 64.0),
+        );
+
+        for handle in nine_slice.handles() {
+            let source_size = sprites.source_size(*handle).unwrap();
+            assert!(source_size.x >= 0.0 && source_size.y >= 0.0);
+        }
+        // The sides shrink to half the source each, the rows fit already
+        let top_left = sprites.get(nine_slice.handles()[0]).unwrap();
+        assert_eq!(top_left.source_size(), ultraviolet::Vec2::new(8.0, 2.0));
+        let center = sprites.get(nine_slice.handles()[4]).unwrap();
+        assert_eq!(center.source_position(), ultraviolet::Vec2::new(8.0, 2.0));
+        assert_eq!(center.source_size(), ultraviolet::Vec2::new(0.0, 0.0));
+        // The destination keeps its full borders
+        let bottom_right = sprites.get(nine_slice.handles()[8]).unwrap();
+        assert_eq!(
+            bottom_right.scale(),
+            ultraviolet::Vec2::new(12.0 / 8.0, 6.0 / 6.0)
+        );
+    }
+
+    #[test]
+    fn removing_skips_pieces_that_are_gone() {
+        let mut sprites = Sprites::new();
+        let desc = SpriteDesc::new((32, 32));
+        let nine_slice = NineSlice::new(
+            &mut sprites,
+            desc,
+            Borders::uniform(4.0),
+            ultraviolet::Vec2::new(64.0, 64.0),
+        );
+        sprites.remove(nine_slice.handles()[0]).unwrap();
+
+        assert_eq!(nine_slice.remove(&mut sprites), None);
+        assert!(sprites.is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::backend::sprite::{Affine2, SpriteHandle, Sprites};
+
+/// Read-only view of a single live sprite, see [`Sprites::get`] and [`Sprites::iter`].
+#[derive(Copy, Clone)]
+pub struct SpriteRef<'a> {
+    pub(super) sprites: &'a Sprites,
+    pub(super) index: usize,
+}
+
+impl<'a> SpriteRef<'a> {
+    pub fn handle(&self) -> SpriteHandle {
+        self.sprites.handles[self.index]
+    }
+    pub fn texture_index(&self) -> u32 {
+        self.sprites.texture_index[self.index]
+    }
+    pub fn source_position(&self) -> ultraviolet::Vec2 {
+        self.sprites.source_position[self.index]
+    }
+    pub fn source_size(&self) -> ultraviolet::Vec2 {
+        self.sprites.source_size[self.index]
+    }
+    pub fn position(&self) -> ultraviolet::Vec2 {
+        self.sprites.position[self.index]
+    }
+    pub fn angle(&self) -> f32 {
+        self.sprites.angle[self.index]
+    }
+    pub fn scale(&self) -> ultraviolet::Vec2 {
+        self.sprites.scale[self.index]
+    }
+    pub fn depth(&self) -> f32 {
+        self.sprites.depth[self.index]
+    }
+    pub fn color(&self) -> [f32; 4] {
+        self.sprites.color[self.index]
+    }
+    pub fn origin(&self) -> ultraviolet::Vec2 {
+        self.sprites.origin[self.index]
+    }
+    pub fn flip_x(&self) -> bool {
+        self.sprites.flip_x[self.index]
+    }
+    pub fn flip_y(&self) -> bool {
+        self.sprites.flip_y[self.index]
+    }
+    pub fn skew(&self) -> ultraviolet::Vec2 {
+        self.sprites.skew[self.index]
+    }
+    pub fn custom_transform(&self) -> Option<Affine2> {
+        self.sprites.custom_transform[self.index]
+    }
+}
+
+/// Mutable view of a single live sprite, see [`Sprites::get_mut`]. Its setters mark the
+/// sprite dirty just like the ones on [`Sprites`].
+pub struct SpriteMut<'a> {
+    pub(super) sprites: &'a mut Sprites,
+    pub(super) handle: SpriteHandle,
+    pub(super) index: usize,
+}
+
+impl<'a> SpriteMut<'a> {
+    pub fn to_ref(&self) -> SpriteRef<'_> {
+        SpriteRef {
+            sprites: self.sprites,
+            index: self.index,
+        }
+    }
+
+    pub fn handle(&self) -> SpriteHandle {
+        self.handle
+    }
+    pub fn texture_index(&self) -> u32 {
+        self.sprites.texture_index[self.index]
+    }
+    pub fn source_position(&self) -> ultraviolet::Vec2 {
+        self.sprites.source_position[self.index]
+    }
+    pub fn source_size(&self) -> ultraviolet::Vec2 {
+        self.sprites.source_size[self.index]
+    }
+    pub fn position(&self) -> ultraviolet::Vec2 {
+        self.sprites.position[self.index]
+    }
+    pub fn angle(&self) -> f32 {
+        self.sprites.angle[self.index]
+    }
+    pub fn scale(&self) -> ultraviolet::Vec2 {
+        self.sprites.scale[self.index]
+    }
+    pub fn depth(&self) -> f32 {
+        self.sprites.depth[self.index]
+    }
+    pub fn color(&self) -> [f32; 4] {
+        self.sprites.color[self.index]
+    }
+    pub fn origin(&self) -> ultraviolet::Vec2 {
+        self.sprites.origin[self.index]
+    }
+    pub fn flip_x(&self) -> bool {
+        self.sprites.flip_x[self.index]
+    }
+    pub fn flip_y(&self) -> bool {
+        self.sprites.flip_y[self.index]
+    }
+    pub fn skew(&self) -> ultraviolet::Vec2 {
+        self.sprites.skew[self.index]
+    }
+    pub fn custom_transform(&self) -> Option<Affine2> {
+        self.sprites.custom_transform[self.index]
+    }
+
+    // The handle was live when the view was created and nothing can remove the sprite
+    // while the view borrows `Sprites`, so the setters can't fail
+    pub fn set_texture_index(&mut self, val: u32) {
+        self.sprites.set_texture_index(self.handle, val);
+    }
+    pub fn set_source_position(&mut self, val: ultraviolet::Vec2) {
+        self.sprites.set_source_position(self.handle, val);
+    }
+    pub fn set_source_size(&mut self, val: ultraviolet::Vec2) {
+        self.sprites.set_source_size(self.handle, val);
+    }
+    pub fn set_position(&mut self, val: ultraviolet::Vec2) {
+        self.sprites.set_position(self.handle, val);
+    }
+    pub fn set_angle(&mut self, val: f32) {
+        self.sprites.set_angle(self.handle, val);
+    }
+    pub fn set_scale(&mut self, val: ultraviolet::Vec2) {
+        self.sprites.set_scale(self.handle, val);
+    }
+    pub fn set_depth(&mut self, val: f32) {
+        self.sprites.set_depth(self.handle, val);
+    }
+    pub fn set_color(&mut self, val: [f32; 4]) {
+        self.sprites.set_color(self.handle, val);
+    }
+    pub fn set_origin(&mut self, val: ultraviolet::Vec2) {
+        self.sprites.set_origin(self.handle, val);
+    }
+    pub fn set_flip_x(&mut self, val: bool) {
+        self.sprites.set_flip_x(self.handle, val);
+    }
+    pub fn set_flip_y(&mut self, val: bool) {
+        self.sprites.set_flip_y(self.handle, val);
+    }
+    pub fn set_skew(&mut self, val: ultraviolet::Vec2) {
+        self.sprites.set_skew(self.handle, val);
+    }
+    pub fn set_custom_transform(&mut self, val: Option<Affine2>) {
+        self.sprites.set_custom_transform(self.handle, val);
+    }
+}
@@ -3,6 +3,7 @@
 pub struct Vertex {
     pub(crate) position: [f32; 3],
     pub(crate) tex_coords: [f32; 2],
+    pub(crate) color: [f32; 4],
 }
 
 impl Vertex {
@@ -21,7 +22,32 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float2,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float4,
+                },
             ],
         }
     }
 }
+
+/// Bottom left and top right corner of the box around `vertices`, where tests check the
+/// quads ended up.
+#[cfg(test)]
+pub(crate) fn bounds(vertices: &[Vertex]) -> ([f32; 2], [f32; 2]) {
+    vertices
+        .iter()
+        .fold(([f32::MAX; 2], [f32::MIN; 2]), |(min, max), vertex| {
+            (
+                [
+                    min[0].min(vertex.position[0]),
+                    min[1].min(vertex.position[1]),
+                ],
+                [
+                    max[0].max(vertex.position[0]),
+                    max[1].max(vertex.position[1]),
+                ],
+            )
+        })
+}
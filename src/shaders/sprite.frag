@@ -2,13 +2,29 @@
 #version 450
 
 layout(location = 0) in vec2 v_tex_coords;
+layout(location = 1) in vec4 v_color;
 layout(location = 0) out vec4 f_color;
 
+layout(set = 0, binding = 0) uniform Globals {
+    mat4 ortho;
+    mat4 transform;
+    float alpha_cutoff;
+    float premultiply;
+} global;
+
 layout(set = 1, binding = 0) uniform texture2D t_diffuse;
 layout(set = 1, binding = 1) uniform sampler s_diffuse;
 
 void main() {
-    f_color = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+    f_color = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords) * v_color;
+
+    // Every blend mode expects premultiplied color, straight alpha textures get it here
+    f_color.rgb *= mix(1.0, f_color.a, global.premultiply);
+
+    // Keep transparent texels out of the depth buffer
+    if (f_color.a < global.alpha_cutoff) {
+        discard;
+    }
 }
 
 
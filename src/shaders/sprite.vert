@@ -4,15 +4,20 @@
 layout(set = 0, binding = 0) uniform Globals {
     mat4 ortho;
     mat4 transform;
+    float alpha_cutoff;
+    float premultiply;
 } global;
 
 layout(location=0) in vec3 a_position;
 layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec4 a_color;
 
 layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
 
 void main() {
     v_tex_coords = a_tex_coords;
+    v_color = a_color;
 
     gl_Position = global.ortho * global.transform * vec4(a_position, 1.0);
 }
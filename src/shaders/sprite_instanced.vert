@@ -0,0 +1,82 @@
+// sprite_instanced.vert
+#version 450
+
+layout(set = 0, binding = 0) uniform Globals {
+    mat4 ortho;
+    mat4 transform;
+    float alpha_cutoff;
+    float premultiply;
+} global;
+
+layout(location=0) in vec2 i_position;
+layout(location=1) in vec2 i_scale;
+layout(location=2) in vec2 i_origin;
+layout(location=3) in vec2 i_source_size;
+layout(location=4) in vec4 i_tex_rect;
+layout(location=5) in vec4 i_color;
+layout(location=6) in float i_angle;
+layout(location=7) in float i_depth;
+layout(location=8) in vec2 i_skew;
+layout(location=9) in vec2 i_flip;
+layout(location=10) in vec2 i_custom_x_axis;
+layout(location=11) in vec2 i_custom_y_axis;
+layout(location=12) in vec2 i_custom_translation;
+layout(location=13) in float i_custom_transform;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
+
+// Corners A, B, D, D, B, C, the same two triangles the CPU path indexes
+const vec2 CORNERS[6] = vec2[6](
+    vec2(0.0, 1.0),
+    vec2(0.0, 0.0),
+    vec2(1.0, 1.0),
+    vec2(1.0, 1.0),
+    vec2(0.0, 0.0),
+    vec2(1.0, 0.0)
+);
+// B and D traded, keeps mirrored quads front facing like the CPU path does
+const vec2 MIRRORED_CORNERS[6] = vec2[6](
+    vec2(0.0, 1.0),
+    vec2(1.0, 1.0),
+    vec2(0.0, 0.0),
+    vec2(0.0, 0.0),
+    vec2(1.0, 1.0),
+    vec2(1.0, 0.0)
+);
+
+void main() {
+    // Maps the unit quad onto the sprite, world = linear * corner + offset
+    mat2 linear;
+    vec2 offset;
+    if (i_custom_transform > 0.5) {
+        // A custom transform maps the sprite's local texels and replaces the rest
+        mat2 custom = mat2(i_custom_x_axis, i_custom_y_axis);
+        linear = custom * mat2(i_source_size.x, 0.0, 0.0, i_source_size.y);
+        offset = i_custom_translation;
+    } else {
+        // Scale around the origin, skew, rotate, then translate. Skewing along x leans
+        // the y axis and the other way around
+        vec2 size = i_source_size * i_scale;
+        vec2 skew = tan(radians(i_skew));
+        mat2 shear = mat2(1.0, skew.y, skew.x, 1.0);
+        float angle = radians(i_angle);
+        mat2 rotation = mat2(cos(angle), sin(angle), -sin(angle), cos(angle));
+        linear = rotation * shear * mat2(size.x, 0.0, 0.0, size.y);
+        offset = i_position - linear * i_origin;
+    }
+
+    vec2 corner = determinant(linear) < 0.0
+        ? MIRRORED_CORNERS[gl_VertexIndex]
+        : CORNERS[gl_VertexIndex];
+    vec2 world = linear * corner + offset;
+
+    // The texture's v axis points down, the quad's y axis up. Flips mirror the UVs, not
+    // the quad
+    vec2 uv = vec2(corner.x, 1.0 - corner.y);
+    uv = mix(uv, 1.0 - uv, i_flip);
+    v_tex_coords = i_tex_rect.xy + uv * i_tex_rect.zw;
+    v_color = i_color;
+
+    gl_Position = global.ortho * global.transform * vec4(world, i_depth, 1.0);
+}
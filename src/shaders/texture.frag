@@ -12,6 +12,9 @@ layout(set = 0, binding = 1) uniform sampler s_diffuse;
 void main() {
     // Changed
     f_color = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
+
+    // Every blend mode expects premultiplied color
+    f_color.rgb *= f_color.a;
 }
 
 